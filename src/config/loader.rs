@@ -0,0 +1,538 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// `ConfigFormat` is the format of a configuration file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigFormat {
+    /// YAML, for files ending in `.yaml` or `.yml`.
+    Yaml,
+    /// TOML, for files ending in `.toml`.
+    Toml,
+    /// JSON, for files ending in `.json`.
+    Json,
+}
+
+impl ConfigFormat {
+    /// Returns the format matching the extension of `path`, if any.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            "toml" => Some(ConfigFormat::Toml),
+            "json" => Some(ConfigFormat::Json),
+            _ => None,
+        }
+    }
+
+    fn parse(self, content: &str) -> anyhow::Result<Value> {
+        let value = match self {
+            ConfigFormat::Yaml => serde_yaml::from_str::<Option<Value>>(content)?.unwrap_or(Value::Null),
+            ConfigFormat::Toml => toml::from_str(content)?,
+            ConfigFormat::Json => serde_json::from_str(content)?,
+        };
+        Ok(value)
+    }
+}
+
+/// The origin of a configuration value, used in error messages.
+#[derive(Clone, Debug, PartialEq)]
+enum Source {
+    Defaults,
+    File(PathBuf),
+    Env(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Defaults => write!(f, "defaults"),
+            Source::File(path) => write!(f, "file `{}`", path.display()),
+            Source::Env(name) => write!(f, "environment variable `{}`", name),
+        }
+    }
+}
+
+/// `ConfigLoader` merges configuration sources into any `Deserialize` struct, such as
+/// a service configuration holding a `DatabaseConfig`, a `RedisConfig` and a `LoggerConfig`.
+///
+/// Sources are applied in the order they are added, later sources overriding earlier
+/// ones key by key: nested tables are merged, while other values are replaced. The
+/// usual order is defaults, then a configuration file, then environment variables.
+///
+/// Environment variables are matched by prefix: with the prefix `APP`, the variable
+/// `APP_DATABASE__HOST` sets the key `database.host`. The prefix is followed by a
+/// single underscore, nested keys are separated by double underscores, and names are
+/// lowercased. Values are kept as strings, so `APP_DATABASE__PASSWORD=0123` sets the
+/// string `"0123"`; only if a key cannot be deserialized from a string, such as a number
+/// or boolean field, is its value parsed as a number or boolean instead.
+///
+/// Errors name the offending key and the source that set it, e.g.
+/// ``Invalid configuration at `database.port` (from environment variable `APP_DATABASE__PORT`): invalid type: string "abc", expected u16``.
+///
+/// # Example
+/// ```rust
+/// use multitool_hg::config::loader::ConfigLoader;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, Default, Deserialize, Serialize)]
+/// struct ServiceConfig {
+///     name: String,
+///     workers: u32,
+/// }
+///
+/// fn main() -> anyhow::Result<()> {
+///     std::env::set_var("MY_SERVICE_WORKERS", "8");
+///     let config: ServiceConfig = ConfigLoader::new()
+///         .defaults(&ServiceConfig { name: "my-service".to_string(), workers: 4 })?
+///         .optional_file("/etc/my-service/config.yaml")?
+///         .env("MY_SERVICE")?
+///         .load()?;
+///
+///     assert_eq!(config.name, "my-service");
+///     assert_eq!(config.workers, 8);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ConfigLoader {
+    value: Map<String, Value>,
+    sources: BTreeMap<String, Source>,
+}
+
+impl ConfigLoader {
+    /// Creates a loader without any source.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses the serialized form of `defaults` as the base configuration.
+    ///
+    /// # Errors
+    /// Returns an error if `defaults` cannot be serialized into a table.
+    pub fn defaults<T: Serialize>(mut self, defaults: &T) -> anyhow::Result<Self> {
+        let value = serde_json::to_value(defaults)?;
+        self.merge_value(value, &Source::Defaults)?;
+        Ok(self)
+    }
+
+    /// Merges the configuration file at `path`, whose format is taken from its extension.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, has an unknown extension, or cannot be parsed.
+    pub fn file(self, path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path).ok_or_else(|| {
+            anyhow::Error::msg(format!("Unknown format of configuration file `{}`; expected .yaml, .yml, .toml or .json.", path.display()))
+        })?;
+        self.file_with_format(path, format)
+    }
+
+    /// Merges the configuration file at `path` if it exists.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn optional_file(self, path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        if path.as_ref().exists() {
+            self.file(path)
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Merges the configuration file at `path`, parsed as `format`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn file_with_format(mut self, path: impl AsRef<Path>, format: ConfigFormat) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| anyhow::Error::msg(format!("Failed to read configuration file `{}`: {}", path.display(), err)))?;
+        let value = format
+            .parse(&content)
+            .map_err(|err| anyhow::Error::msg(format!("Failed to parse configuration file `{}`: {}", path.display(), err)))?;
+        self.merge_value(value, &Source::File(path.to_path_buf()))?;
+        Ok(self)
+    }
+
+    /// Merges the environment variables starting with `prefix` followed by an underscore.
+    ///
+    /// Variables that do not start with the prefix are ignored, even if they are not valid UTF-8.
+    ///
+    /// # Errors
+    /// Returns an error naming the variable if a variable starting with the prefix is not valid UTF-8.
+    pub fn env(self, prefix: &str) -> anyhow::Result<Self> {
+        self.env_vars_os(prefix, std::env::vars_os())
+    }
+
+    /// Merges the variables of `vars` starting with `prefix`, skipping any other variable
+    /// whether or not it is valid UTF-8.
+    fn env_vars_os(self, prefix: &str, vars: impl IntoIterator<Item = (OsString, OsString)>) -> anyhow::Result<Self> {
+        let prefix = format!("{}_", prefix);
+        let mut matching = Vec::new();
+        for (name, value) in vars {
+            if !name.as_encoded_bytes().starts_with(prefix.as_bytes()) {
+                continue;
+            }
+            let name = name.into_string().map_err(|name| {
+                anyhow::Error::msg(format!("Name of environment variable `{}` is not valid UTF-8.", name.to_string_lossy()))
+            })?;
+            let value = value
+                .into_string()
+                .map_err(|_| anyhow::Error::msg(format!("Value of environment variable `{}` is not valid UTF-8.", name)))?;
+            matching.push((name, value));
+        }
+        Ok(self.env_vars(&prefix[..prefix.len() - 1], matching))
+    }
+
+    /// Merges the variables of `vars` starting with `prefix` followed by an underscore,
+    /// as if they were environment variables.
+    pub fn env_vars<K, V>(mut self, prefix: &str, vars: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let prefix = format!("{}_", prefix);
+        let mut vars: Vec<(String, String)> = vars.into_iter().map(|(name, value)| (name.into(), value.into())).collect();
+        vars.sort();
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(&prefix) else {
+                continue;
+            };
+            let path: Vec<String> = key.split("__").map(str::to_lowercase).collect();
+            if path.iter().any(String::is_empty) {
+                continue;
+            }
+            self.set(&path, value, Source::Env(name.clone()));
+        }
+        self
+    }
+
+    /// Deserializes the merged configuration into `T`.
+    ///
+    /// # Errors
+    /// Returns an error naming the offending key and the source that set it if the
+    /// merged configuration does not match `T`.
+    pub fn load<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        let mut value = Value::Object(self.value.clone());
+        loop {
+            let err = match serde_path_to_error::deserialize(&value) {
+                Ok(config) => return Ok(config),
+                Err(err) => err,
+            };
+            let key = err.path().to_string();
+            if self.retype_env_value(&mut value, &key) {
+                continue;
+            }
+            let message = err.inner().to_string();
+            return Err(match self.source_of(&key) {
+                Some(source) => anyhow::Error::msg(format!("Invalid configuration at `{}` (from {}): {}", key, source, message)),
+                None => anyhow::Error::msg(format!("Invalid configuration at `{}`: {}", key, message)),
+            });
+        }
+    }
+
+    /// Replaces the string at `key` with the number or boolean it spells, if the key
+    /// was set by an environment variable. Returns whether the value was replaced.
+    fn retype_env_value(&self, value: &mut Value, key: &str) -> bool {
+        if !matches!(self.sources.get(key), Some(Source::Env(_))) {
+            return false;
+        }
+        let pointer = format!("/{}", key.replace('.', "/"));
+        let Some(entry) = value.pointer_mut(&pointer) else {
+            return false;
+        };
+        let Value::String(raw) = entry else {
+            return false;
+        };
+        match parse_scalar(raw) {
+            Some(typed) => {
+                *entry = typed;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn merge_value(&mut self, value: Value, source: &Source) -> anyhow::Result<()> {
+        match value {
+            Value::Object(map) => {
+                merge(&mut self.value, map, "", source, &mut self.sources);
+                Ok(())
+            }
+            Value::Null => Ok(()),
+            _ => Err(anyhow::Error::msg(format!("Configuration from {} must be a table of keys.", source))),
+        }
+    }
+
+    /// Sets the key at `path` to a string value.
+    fn set(&mut self, path: &[String], raw: String, source: Source) {
+        let (last, parents) = path.split_last().expect("path is not empty");
+        let mut map = &mut self.value;
+        for key in parents {
+            let entry = map.entry(key.clone()).or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            map = entry.as_object_mut().expect("entry is an object");
+        }
+        map.insert(last.clone(), Value::String(raw));
+
+        let key = path.join(".");
+        self.sources.retain(|existing, _| !is_within(existing, &key));
+        self.sources.insert(key, source);
+    }
+
+    /// Returns the source of `key`, or of the closest parent key that was set as a whole.
+    fn source_of(&self, key: &str) -> Option<&Source> {
+        let mut key = key;
+        if key == "." {
+            return None;
+        }
+        loop {
+            if let Some(source) = self.sources.get(key) {
+                return Some(source);
+            }
+            key = &key[..key.rfind('.')?];
+        }
+    }
+}
+
+/// Merges `from` into `into`, recording the source of every key. A merged table is
+/// attributed to the latest source that set it.
+fn merge(into: &mut Map<String, Value>, from: Map<String, Value>, prefix: &str, source: &Source, sources: &mut BTreeMap<String, Source>) {
+    for (key, value) in from {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            Value::Object(map) => {
+                let entry = into.entry(key).or_insert_with(|| Value::Object(Map::new()));
+                if !entry.is_object() {
+                    sources.retain(|existing, _| !is_within(existing, &path));
+                    *entry = Value::Object(Map::new());
+                }
+                sources.insert(path.clone(), source.clone());
+                let entry = entry.as_object_mut().expect("entry is an object");
+                merge(entry, map, &path, source, sources);
+            }
+            value => {
+                sources.retain(|existing, _| !is_within(existing, &path));
+                sources.insert(path, source.clone());
+                into.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Returns whether `key` is `parent` or one of its nested keys.
+fn is_within(key: &str, parent: &str) -> bool {
+    key == parent || key.strip_prefix(parent).is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Parses an environment variable as a boolean, ignoring case, or a number, if it spells one.
+fn parse_scalar(raw: &str) -> Option<Value> {
+    if raw.eq_ignore_ascii_case("true") {
+        return Some(Value::Bool(true));
+    }
+    if raw.eq_ignore_ascii_case("false") {
+        return Some(Value::Bool(false));
+    }
+    if let Ok(number) = raw.parse::<i64>() {
+        return Some(Value::from(number));
+    }
+    if let Ok(number) = raw.parse::<u64>() {
+        return Some(Value::from(number));
+    }
+    raw.parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite() && raw.contains('.'))
+        .map(Value::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use super::*;
+
+    #[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+    struct Database {
+        host: String,
+        port: u16,
+        password: String,
+    }
+
+    #[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+    struct Service {
+        name: String,
+        debug: bool,
+        database: Database,
+        token: Option<String>,
+    }
+
+    fn defaults() -> Service {
+        Service {
+            name: "service".to_string(),
+            debug: false,
+            database: Database {
+                host: "localhost".to_string(),
+                port: 5432,
+                password: String::new(),
+            },
+            token: None,
+        }
+    }
+
+    fn write_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("multitool-config-{}-{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// Test that a file and environment variables override the defaults key by key.
+    #[test]
+    fn test_layered_sources() {
+        let path = write_file("layered.yaml", "name: billing\ndatabase:\n  host: db.internal\n");
+        let config: Service = ConfigLoader::new()
+            .defaults(&defaults())
+            .unwrap()
+            .file(&path)
+            .unwrap()
+            .env_vars(
+                "APP",
+                [
+                    ("APP_DEBUG", "true"),
+                    ("APP_DATABASE__PORT", "6432"),
+                    ("APP_DATABASE__PASSWORD", "12345"),
+                    ("OTHER_NAME", "ignored"),
+                ],
+            )
+            .load()
+            .unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            config,
+            Service {
+                name: "billing".to_string(),
+                debug: true,
+                database: Database {
+                    host: "db.internal".to_string(),
+                    port: 6432,
+                    password: "12345".to_string(),
+                },
+                token: None,
+            }
+        );
+    }
+
+    /// Test that values spelling numbers stay strings for string fields, including
+    /// optional fields whose default is `null` and values with leading zeros.
+    #[test]
+    fn test_env_strings_stay_strings() {
+        let config: Service = ConfigLoader::new()
+            .defaults(&defaults())
+            .unwrap()
+            .env_vars("APP", [("APP_TOKEN", "123456"), ("APP_DATABASE__PASSWORD", "0123")])
+            .load()
+            .unwrap();
+        assert_eq!(config.token.as_deref(), Some("123456"));
+        assert_eq!(config.database.password, "0123");
+
+        #[derive(Debug, Deserialize)]
+        struct Partial {
+            password: String,
+            port: Option<u16>,
+            debug: bool,
+        }
+        let config: Partial = ConfigLoader::new()
+            .env_vars("APP", [("APP_PASSWORD", "0123"), ("APP_PORT", "6432"), ("APP_DEBUG", "TRUE")])
+            .load()
+            .unwrap();
+        assert_eq!(config.password, "0123");
+        assert_eq!(config.port, Some(6432));
+        assert!(config.debug);
+    }
+
+    /// Test loading TOML and JSON files.
+    #[test]
+    fn test_file_formats() {
+        let toml = write_file("service.toml", "name = \"toml\"\n[database]\nport = 1\n");
+        let json = write_file("service.json", r#"{"name": "json", "database": {"host": "json-host"}}"#);
+        let config: Service = ConfigLoader::new()
+            .defaults(&defaults())
+            .unwrap()
+            .file(&toml)
+            .unwrap()
+            .file(&json)
+            .unwrap()
+            .load()
+            .unwrap();
+        std::fs::remove_file(toml).unwrap();
+        std::fs::remove_file(json).unwrap();
+
+        assert_eq!(config.name, "json");
+        assert_eq!(config.database.host, "json-host");
+        assert_eq!(config.database.port, 1);
+    }
+
+    /// Test that errors name the offending key and its source.
+    #[test]
+    fn test_errors_name_key_and_source() {
+        let err = ConfigLoader::new()
+            .defaults(&defaults())
+            .unwrap()
+            .env_vars("APP", [("APP_DATABASE__PORT", "not-a-port")])
+            .load::<Service>()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("`database.port`"), "{}", err);
+        assert!(err.contains("environment variable `APP_DATABASE__PORT`"), "{}", err);
+
+        let path = write_file("invalid.yaml", "database:\n  port: 70000\n");
+        let err = ConfigLoader::new().defaults(&defaults()).unwrap().file(&path).unwrap().load::<Service>().unwrap_err().to_string();
+        assert!(err.contains("`database.port`"), "{}", err);
+        assert!(err.contains(&format!("file `{}`", path.display())), "{}", err);
+        std::fs::remove_file(&path).unwrap();
+
+        // A table replaced by a file is attributed to the file, not to the defaults it overrode.
+        let path = write_file("partial.yaml", "database:\n  host: db.internal\n");
+        let err = ConfigLoader::new()
+            .defaults(&serde_json::json!({"name": "api", "debug": false, "database": {"host": "localhost"}}))
+            .unwrap()
+            .file(&path)
+            .unwrap()
+            .load::<Service>()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("`database`"), "{}", err);
+        assert!(err.contains(&format!("file `{}`", path.display())), "{}", err);
+        std::fs::remove_file(&path).unwrap();
+
+        let err = ConfigLoader::new().file("config.ini").unwrap_err().to_string();
+        assert!(err.contains("Unknown format"), "{}", err);
+        assert!(ConfigLoader::new().optional_file("/nonexistent/config.yaml").is_ok());
+    }
+
+    /// Test that non-UTF-8 variables are skipped unless they start with the prefix.
+    #[cfg(unix)]
+    #[test]
+    fn test_env_non_utf8() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let invalid = || OsString::from_vec(vec![b'a', 0xff]);
+        let config: Service = ConfigLoader::new()
+            .defaults(&defaults())
+            .unwrap()
+            .env_vars_os("APP", [(invalid(), invalid()), ("OTHER".into(), invalid()), ("APP_NAME".into(), "api".into())])
+            .unwrap()
+            .load()
+            .unwrap();
+        assert_eq!(config.name, "api");
+
+        let err = ConfigLoader::new().env_vars_os("APP", [("APP_NAME".into(), invalid())]).unwrap_err();
+        assert!(err.to_string().contains("`APP_NAME` is not valid UTF-8"), "{}", err);
+    }
+}
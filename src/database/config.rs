@@ -7,7 +7,8 @@ use std::time::Duration;
 /// to PostgreSQL or another `sqlx`-supported database.
 ///
 /// Configuration fields can be loaded from various sources, such as YAML, JSON
-/// configuration files, or from environment variables.
+/// configuration files, or from environment variables, for example with
+/// `ConfigLoader` from the `config` feature.
 ///
 /// Example configuration in YAML:
 ///
@@ -19,36 +20,299 @@ use std::time::Duration;
 /// database: test
 /// max_open_cons: 10
 /// min_idle_cons: 5
-/// conn_max_lifetime:
-///   secs: 900
-///   nanos: 0
-/// connection_timeout:
-///   secs: 15
-///   nanos: 0
-/// idle_timeout:
-///   secs: 3600
-///   nanos: 0
+/// conn_max_lifetime: 15m
+/// connection_timeout: 15s
+/// idle_timeout: 1h
 /// ```
-#[derive(Debug, Deserialize, Serialize)]
+///
+/// Durations are written like `15m`, `30s` or `1h30m`; plain integers are seconds, and
+/// the `{secs, nanos}` form is accepted as well.
+///
+/// Only `host` and `database` are required; the port, credentials, pool sizes and
+/// timeouts default to the values of [`DatabaseConfig::default`]:
+///
+/// ```yaml
+/// host: localhost
+/// username: user
+/// password: password
+/// database: test
+/// ```
+///
+/// In code, [`DatabaseConfig::builder`] requires the same two fields, takes the other
+/// settings from the defaults and validates the result:
+///
+/// ```rust
+/// use multitool_hg::database::config::DatabaseConfig;
+///
+/// let config = DatabaseConfig::builder()
+///     .host("db.internal")
+///     .username("service")
+///     .password("password")
+///     .database("orders")
+///     .max_open_cons(20)
+///     .build()
+///     .unwrap();
+/// assert_eq!(config.port, 5432);
+/// ```
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct DatabaseConfig {
     /// The database host address (e.g. localhost).
     pub host: String,
-    /// The port to connect to the database (e.g. 5432 for PostgreSQL).
+    /// The port to connect to the database (default is 5432, the PostgreSQL port).
+    #[serde(default = "DatabaseConfig::default_port")]
     pub port: u16,
-    /// The username to connect to the database.
+    /// The username to connect to the database (default is postgres).
+    #[serde(default = "DatabaseConfig::default_username")]
     pub username: String,
-    /// The password to connect to the database.
+    /// The password to connect to the database (default is empty).
+    #[serde(default)]
     pub password: String,
     /// The name of the database to connect to.
     pub database: String,
-    /// The maximum number of open connections in the pool.
+    /// The maximum number of open connections in the pool (default is 10).
+    #[serde(default = "DatabaseConfig::default_max_open_cons")]
     pub max_open_cons: u32,
-    /// The minimum number of idle connections in the pool.
+    /// The minimum number of idle connections in the pool (default is 0).
+    #[serde(default)]
     pub min_idle_cons: u32,
-    /// The maximum connection lifetime.
+    /// The maximum connection lifetime (default is 30 minutes).
+    #[serde(with = "crate::duration", default = "DatabaseConfig::default_conn_max_lifetime")]
     pub conn_max_lifetime: Duration,
-    /// The timeout for getting a connection from the pool.
+    /// The timeout for getting a connection from the pool (default is 30 seconds).
+    #[serde(with = "crate::duration", default = "DatabaseConfig::default_connection_timeout")]
     pub connection_timeout: Duration,
-    /// The idle connection timeout in the pool, after which the connection can be closed.
+    /// The idle connection timeout in the pool, after which the connection can be closed (default is 10 minutes).
+    #[serde(with = "crate::duration", default = "DatabaseConfig::default_idle_timeout")]
     pub idle_timeout: Duration,
 }
+
+impl Default for DatabaseConfig {
+    /// Returns a configuration for the `postgres` database and user on `localhost:5432`,
+    /// with an empty password and the pool settings of `sqlx`.
+    fn default() -> Self {
+        DatabaseConfig {
+            host: "localhost".to_string(),
+            port: Self::default_port(),
+            username: Self::default_username(),
+            password: String::new(),
+            database: "postgres".to_string(),
+            max_open_cons: Self::default_max_open_cons(),
+            min_idle_cons: 0,
+            conn_max_lifetime: Self::default_conn_max_lifetime(),
+            connection_timeout: Self::default_connection_timeout(),
+            idle_timeout: Self::default_idle_timeout(),
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// Returns a builder taking the port, credentials, pool sizes and timeouts from the
+    /// default configuration.
+    ///
+    /// The host and database must be set before building.
+    pub fn builder() -> DatabaseConfigBuilder {
+        DatabaseConfigBuilder::default()
+    }
+
+    fn default_port() -> u16 {
+        5432
+    }
+
+    fn default_username() -> String {
+        "postgres".to_string()
+    }
+
+    fn default_max_open_cons() -> u32 {
+        10
+    }
+
+    fn default_conn_max_lifetime() -> Duration {
+        Duration::from_secs(30 * 60)
+    }
+
+    fn default_connection_timeout() -> Duration {
+        Duration::from_secs(30)
+    }
+
+    fn default_idle_timeout() -> Duration {
+        Duration::from_secs(10 * 60)
+    }
+
+    /// Checks the validity of the configuration.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the configuration is valid, or an `anyhow::Error` naming the invalid field.
+    pub fn check(&self) -> anyhow::Result<()> {
+        for (name, value) in [("host", &self.host), ("username", &self.username), ("database", &self.database)] {
+            if value.is_empty() {
+                return Err(anyhow::Error::msg(format!("Field `{}` must not be empty.", name)));
+            }
+        }
+        if self.port == 0 {
+            return Err(anyhow::Error::msg("Field `port` must be greater than zero."));
+        }
+        if self.max_open_cons == 0 {
+            return Err(anyhow::Error::msg("Field `max_open_cons` must be greater than zero."));
+        }
+        if self.min_idle_cons > self.max_open_cons {
+            return Err(anyhow::Error::msg(format!(
+                "Field `min_idle_cons` ({}) must not exceed `max_open_cons` ({}).",
+                self.min_idle_cons,
+                self.max_open_cons
+            )));
+        }
+        for (name, value) in [("conn_max_lifetime", self.conn_max_lifetime), ("connection_timeout", self.connection_timeout)] {
+            if value.is_zero() {
+                return Err(anyhow::Error::msg(format!("Field `{}` must be greater than zero.", name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `DatabaseConfigBuilder` builds a [`DatabaseConfig`], validating it on [`build`](Self::build).
+///
+/// Like a configuration file, the builder requires the host and database; the other
+/// settings default to the values of [`DatabaseConfig::default`].
+#[derive(Clone, Debug, Default)]
+pub struct DatabaseConfigBuilder {
+    host: Option<String>,
+    database: Option<String>,
+    config: DatabaseConfig,
+}
+
+impl DatabaseConfigBuilder {
+    /// Sets the database host address.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    /// Sets the port to connect to.
+    pub fn port(mut self, port: u16) -> Self {
+        self.config.port = port;
+        self
+    }
+
+    /// Sets the username to connect with.
+    pub fn username(mut self, username: impl Into<String>) -> Self {
+        self.config.username = username.into();
+        self
+    }
+
+    /// Sets the password to connect with.
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.config.password = password.into();
+        self
+    }
+
+    /// Sets the name of the database to connect to.
+    pub fn database(mut self, database: impl Into<String>) -> Self {
+        self.database = Some(database.into());
+        self
+    }
+
+    /// Sets the maximum number of open connections in the pool.
+    pub fn max_open_cons(mut self, max_open_cons: u32) -> Self {
+        self.config.max_open_cons = max_open_cons;
+        self
+    }
+
+    /// Sets the minimum number of idle connections in the pool.
+    pub fn min_idle_cons(mut self, min_idle_cons: u32) -> Self {
+        self.config.min_idle_cons = min_idle_cons;
+        self
+    }
+
+    /// Sets the maximum connection lifetime.
+    pub fn conn_max_lifetime(mut self, conn_max_lifetime: Duration) -> Self {
+        self.config.conn_max_lifetime = conn_max_lifetime;
+        self
+    }
+
+    /// Sets the timeout for getting a connection from the pool.
+    pub fn connection_timeout(mut self, connection_timeout: Duration) -> Self {
+        self.config.connection_timeout = connection_timeout;
+        self
+    }
+
+    /// Sets the idle connection timeout in the pool.
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.config.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Validates and returns the configuration.
+    ///
+    /// # Errors
+    /// Returns an error naming the required field that was not set, or the invalid field,
+    /// as [`DatabaseConfig::check`] does.
+    pub fn build(self) -> anyhow::Result<DatabaseConfig> {
+        let required = |name: &str, value: Option<String>| {
+            value.ok_or_else(|| anyhow::Error::msg(format!("Field `{}` is required.", name)))
+        };
+        let config = DatabaseConfig {
+            host: required("host", self.host)?,
+            database: required("database", self.database)?,
+            ..self.config
+        };
+        config.check()?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a minimal configuration takes the defaults for the other fields.
+    #[test]
+    fn test_deserialize_minimal() {
+        let config: DatabaseConfig = serde_json::from_str(r#"{"host": "db.internal", "database": "orders"}"#).unwrap();
+
+        assert_eq!(
+            config,
+            DatabaseConfig {
+                host: "db.internal".to_string(),
+                database: "orders".to_string(),
+                ..DatabaseConfig::default()
+            }
+        );
+        assert!(config.check().is_ok());
+        assert!(serde_json::from_str::<DatabaseConfig>(r#"{"host": "db.internal"}"#).is_err());
+    }
+
+    /// Test that the builder applies its settings and validates the result.
+    #[test]
+    fn test_builder() {
+        let builder = DatabaseConfig::builder()
+            .host("db.internal")
+            .username("service")
+            .password("secret")
+            .database("orders");
+        let config = builder
+            .clone()
+            .max_open_cons(20)
+            .min_idle_cons(5)
+            .connection_timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        assert_eq!(config.host, "db.internal");
+        assert_eq!(config.database, "orders");
+        assert_eq!(config.max_open_cons, 20);
+        assert_eq!(config.min_idle_cons, 5);
+        assert_eq!(config.connection_timeout, Duration::from_secs(5));
+        assert_eq!(config.port, 5432);
+
+        let config = DatabaseConfig::builder().host("db.internal").database("orders").build().unwrap();
+        assert_eq!((config.username.as_str(), config.password.as_str()), ("postgres", ""));
+        let err = DatabaseConfig::builder().host("db.internal").build().unwrap_err();
+        assert!(err.to_string().contains("`database` is required"), "{}", err);
+        let err = builder.clone().host("").build().unwrap_err();
+        assert!(err.to_string().contains("`host`"), "{}", err);
+        let err = builder.clone().max_open_cons(2).min_idle_cons(3).build().unwrap_err();
+        assert!(err.to_string().contains("`min_idle_cons`"), "{}", err);
+        assert!(builder.connection_timeout(Duration::ZERO).build().is_err());
+    }
+}
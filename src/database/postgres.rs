@@ -17,8 +17,8 @@ use crate::database::config::DatabaseConfig;
 /// A `Result` containing either a `PgPool` on success or an `anyhow::Error` on failure.
 ///
 /// ### Errors
-/// This function returns an error if the connection to the database cannot be established,
-/// or if the connection pool options are invalid.
+/// This function returns an error if the configuration is invalid (see `DatabaseConfig::check`),
+/// or if the connection to the database cannot be established.
 ///
 /// ### Example
 ///
@@ -29,18 +29,14 @@ use crate::database::config::DatabaseConfig;
 ///
 /// #[tokio::main]
 /// async fn main() -> Result<(), anyhow::Error> {
-///     let config = DatabaseConfig {
-///         host: "127.0.0.1".to_string(),
-///         port: 5432,
-///         username: "user".to_string(),
-///         password: "password".to_string(),
-///         database: "test".to_string(),
-///         max_open_cons: 10,
-///         min_idle_cons: 5,
-///         conn_max_lifetime: Duration::from_secs(900),
-///         connection_timeout: Duration::from_secs(15),
-///         idle_timeout: Duration::from_secs(3600),
-///     };
+///     let config = DatabaseConfig::builder()
+///         .host("127.0.0.1")
+///         .username("user")
+///         .password("password")
+///         .database("test")
+///         .min_idle_cons(5)
+///         .connection_timeout(Duration::from_secs(15))
+///         .build()?;
 ///
 ///     let pool = new_postgres_pool(config).await?;
 ///
@@ -48,6 +44,7 @@ use crate::database::config::DatabaseConfig;
 /// }
 /// ```
 pub async fn new_postgres_pool(config: DatabaseConfig) -> Result<PgPool, anyhow::Error> {
+    config.check()?;
     let connect_options = PgConnectOptions::new()
         .username(&config.username)
         .password(&config.password)
@@ -65,3 +62,16 @@ pub async fn new_postgres_pool(config: DatabaseConfig) -> Result<PgPool, anyhow:
         .await?;
     Ok(pool)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that an invalid configuration is rejected before connecting.
+    #[tokio::test]
+    async fn test_invalid_config() {
+        let config = DatabaseConfig { max_open_cons: 0, ..DatabaseConfig::default() };
+        let err = new_postgres_pool(config).await.unwrap_err();
+        assert!(err.to_string().contains("max_open_cons"), "{}", err);
+    }
+}
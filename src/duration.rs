@@ -0,0 +1,291 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// The supported units, from the largest to the smallest.
+const UNITS: [(&str, Duration); 7] = [
+    ("d", Duration::from_secs(86_400)),
+    ("h", Duration::from_secs(3_600)),
+    ("m", Duration::from_secs(60)),
+    ("s", Duration::from_secs(1)),
+    ("ms", Duration::from_millis(1)),
+    ("us", Duration::from_micros(1)),
+    ("ns", Duration::from_nanos(1)),
+];
+
+/// Parses a duration such as `15m`, `1h30m`, `1.5s` or `250ms`. A bare number, such as
+/// `90` or `1.5`, is a number of seconds. The units are `d`, `h`, `m`, `s`, `ms`, `us` and `ns`.
+///
+/// # Errors
+/// Returns an error if the string is empty, contains an unknown or missing unit, or overflows.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use multitool_hg::duration::parse_duration;
+///
+/// assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+/// assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+/// assert_eq!(parse_duration("1.5").unwrap(), Duration::from_millis(1500));
+/// ```
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let input = s.trim();
+    if input.is_empty() {
+        return Err("Invalid duration: empty string".to_string());
+    }
+    if let Ok(secs) = input.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    if input.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        let secs: f64 = input.parse().map_err(|_| format!("Invalid duration `{}`: malformed number", s))?;
+        return Duration::try_from_secs_f64(secs).map_err(|err| format!("Invalid duration `{}`: {}", s, err));
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let unit_len = rest[number_len..].find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len() - number_len);
+        let (number, unit) = (&rest[..number_len], &rest[number_len..number_len + unit_len]);
+        let value: f64 = number
+            .parse()
+            .map_err(|_| format!("Invalid duration `{}`: expected a number before `{}`", s, unit))?;
+        if unit.is_empty() {
+            return Err(format!("Invalid duration `{}`: missing unit after `{}`", s, number));
+        }
+        let scale = UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, scale)| *scale)
+            .ok_or_else(|| format!("Invalid duration `{}`: unknown unit `{}`, expected one of d, h, m, s, ms, us, ns", s, unit))?;
+        let part = Duration::try_from_secs_f64(scale.as_secs_f64() * value)
+            .map_err(|err| format!("Invalid duration `{}`: {}", s, err))?;
+        total = total
+            .checked_add(part)
+            .ok_or_else(|| format!("Invalid duration `{}`: overflow", s))?;
+        rest = rest[number_len + unit_len..].trim_start();
+    }
+    Ok(total)
+}
+
+/// Formats a duration with the largest units first, e.g. `1h30m` or `1s500ms`.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use multitool_hg::duration::format_duration;
+///
+/// assert_eq!(format_duration(Duration::from_secs(5400)), "1h30m");
+/// ```
+pub fn format_duration(duration: Duration) -> String {
+    if duration.is_zero() {
+        return "0s".to_string();
+    }
+    let mut output = String::new();
+    let mut rest = duration.as_nanos();
+    for (name, scale) in UNITS {
+        let scale = scale.as_nanos();
+        if rest >= scale {
+            output.push_str(&format!("{}{}", rest / scale, name));
+            rest %= scale;
+        }
+    }
+    output
+}
+
+/// Serializes a duration as a human-readable string such as `1h30m`.
+///
+/// Use with `#[serde(with = "multitool_hg::duration")]` on a `Duration` field.
+pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_duration(*duration))
+}
+
+/// Deserializes a duration from a human-readable string such as `15m`, `30s` or `1h30m`,
+/// a number of seconds, or serde's default `{secs, nanos}` form.
+///
+/// Use with `#[serde(with = "multitool_hg::duration")]` on a `Duration` field.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    deserializer.deserialize_any(DurationVisitor)
+}
+
+/// Serializes and deserializes an `Option<Duration>` like [`serialize`] and [`deserialize`].
+///
+/// Use with `#[serde(default, with = "multitool_hg::duration::option")]`.
+pub mod option {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::HumanDuration;
+
+    /// Serializes a duration, if any, as a human-readable string.
+    pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        duration.map(HumanDuration).serialize(serializer)
+    }
+
+    /// Deserializes an optional human-readable duration.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<HumanDuration>::deserialize(deserializer)?.map(|duration| duration.0))
+    }
+}
+
+/// Serializes and deserializes a `BTreeMap` of durations like [`serialize`] and [`deserialize`].
+///
+/// Use with `#[serde(with = "multitool_hg::duration::map")]`.
+pub mod map {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::HumanDuration;
+
+    /// Serializes every duration of the map as a human-readable string.
+    pub fn serialize<K, S>(durations: &BTreeMap<K, Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_map(durations.iter().map(|(key, duration)| (key, HumanDuration(*duration))))
+    }
+
+    /// Deserializes a map of human-readable durations.
+    pub fn deserialize<'de, K, D>(deserializer: D) -> Result<BTreeMap<K, Duration>, D::Error>
+    where
+        K: Deserialize<'de> + Ord,
+        D: Deserializer<'de>,
+    {
+        let durations = BTreeMap::<K, HumanDuration>::deserialize(deserializer)?;
+        Ok(durations.into_iter().map(|(key, duration)| (key, duration.0)).collect())
+    }
+}
+
+/// A duration (de)serialized in the human-readable form, for use inside containers.
+#[derive(Deserialize, serde::Serialize)]
+struct HumanDuration(#[serde(with = "self")] Duration);
+
+struct DurationVisitor;
+
+impl<'de> Visitor<'de> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a duration such as `15m`, a number of seconds, or `{{secs, nanos}}`")
+    }
+
+    fn visit_u64<E: de::Error>(self, secs: u64) -> Result<Duration, E> {
+        Ok(Duration::from_secs(secs))
+    }
+
+    fn visit_i64<E: de::Error>(self, secs: i64) -> Result<Duration, E> {
+        u64::try_from(secs)
+            .map(Duration::from_secs)
+            .map_err(|_| E::custom(format!("Invalid duration: {} is negative", secs)))
+    }
+
+    fn visit_f64<E: de::Error>(self, secs: f64) -> Result<Duration, E> {
+        Duration::try_from_secs_f64(secs).map_err(|err| E::custom(format!("Invalid duration {}: {}", secs, err)))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Duration, E> {
+        parse_duration(s).map_err(E::custom)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Duration, A::Error> {
+        Duration::deserialize(de::value::MapAccessDeserializer::new(map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Config {
+        #[serde(with = "super")]
+        timeout: Duration,
+    }
+
+    /// Test parsing human-readable durations.
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("1h 30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86_400));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("42").unwrap(), Duration::from_secs(42));
+        assert_eq!(parse_duration("1.5").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration(" 0.25 ").unwrap(), Duration::from_millis(250));
+        assert!(parse_duration("1.2.3").is_err());
+        assert!(parse_duration("1h30").unwrap_err().contains("missing unit after `30`"));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("15 minutes").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("-5s").is_err());
+    }
+
+    /// Test that formatted durations parse back to the same value.
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+        assert_eq!(format_duration(Duration::from_secs(900)), "15m");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1s500ms");
+        for duration in [Duration::from_secs(93_784), Duration::from_nanos(1_000_001), Duration::from_secs(3600)] {
+            assert_eq!(parse_duration(&format_duration(duration)).unwrap(), duration);
+        }
+    }
+
+    /// Test deserializing strings, integers and the `{secs, nanos}` form.
+    #[test]
+    fn test_deserialize() {
+        let parse = |json: &str| serde_json::from_str::<Config>(json).map(|config| config.timeout);
+        assert_eq!(parse(r#"{"timeout": "1h30m"}"#).unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse(r#"{"timeout": 60}"#).unwrap(), Duration::from_secs(60));
+        assert_eq!(parse(r#"{"timeout": {"secs": 15, "nanos": 0}}"#).unwrap(), Duration::from_secs(15));
+        assert!(parse(r#"{"timeout": -1}"#).is_err());
+        assert!(parse(r#"{"timeout": "soon"}"#).is_err());
+
+        let json = serde_json::to_string(&Config { timeout: Duration::from_secs(900) }).unwrap();
+        assert_eq!(json, r#"{"timeout":"15m"}"#);
+    }
+
+    /// Test that a string and a float of seconds deserialize to the same duration.
+    #[test]
+    fn test_deserialize_decimal_seconds() {
+        let parse = |json: &str| serde_json::from_str::<Config>(json).map(|config| config.timeout).unwrap();
+        assert_eq!(parse(r#"{"timeout": 1.5}"#), parse(r#"{"timeout": "1.5"}"#));
+    }
+
+    /// Test (de)serializing optional durations and maps of durations.
+    #[test]
+    fn test_containers() {
+        use std::collections::BTreeMap;
+
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct Thresholds {
+            #[serde(default, with = "super::option")]
+            default: Option<Duration>,
+            #[serde(default, with = "super::map")]
+            by_name: BTreeMap<String, Duration>,
+        }
+
+        let config: Thresholds = serde_json::from_str(r#"{"default": "1s", "by_name": {"db": "200ms", "redis": 2}}"#).unwrap();
+        assert_eq!(config.default, Some(Duration::from_secs(1)));
+        assert_eq!(config.by_name["db"], Duration::from_millis(200));
+        assert_eq!(config.by_name["redis"], Duration::from_secs(2));
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            r#"{"default":"1s","by_name":{"db":"200ms","redis":"2s"}}"#
+        );
+
+        let config: Thresholds = serde_json::from_str(r#"{"default": null}"#).unwrap();
+        assert_eq!(config, Thresholds { default: None, by_name: BTreeMap::new() });
+        assert!(serde_json::from_str::<Thresholds>(r#"{"default": "soon"}"#).is_err());
+    }
+}
@@ -6,3 +6,9 @@ pub mod database;
 
 #[cfg(feature = "rediska")]
 pub mod rediska;
+
+#[cfg(feature = "config")]
+pub mod config;
+
+#[cfg(feature = "serde")]
+pub mod duration;
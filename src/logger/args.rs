@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+use crate::logger::config::LoggerConfig;
+use crate::logger::file::FileLogConfig;
+use crate::logger::guard::LoggerGuard;
+use crate::logger::sink::Sink;
+use crate::logger::tracer_logger::{new_tracer_logger_from_config, LogDirectives, LogFormat, LogLevel};
+
+/// `LoggingArgs` is a group of command-line arguments that configure the logger.
+///
+/// Flatten it into a service's CLI to get `--log-level`, `--log-format`, `--log-filter`,
+/// `--log-file` and `--no-color`. Each argument falls back to an environment variable
+/// (`LOG_LEVEL`, `LOG_FORMAT`, `LOG_FILTER`, `LOG_FILE` and `NO_COLOR`) when it is not
+/// given on the command line.
+///
+/// # Example
+/// ```rust
+/// use clap::Parser;
+/// use multitool_hg::logger::args::LoggingArgs;
+///
+/// #[derive(Parser)]
+/// struct Cli {
+///     #[command(flatten)]
+///     logging: LoggingArgs,
+/// }
+///
+/// fn main() -> anyhow::Result<()> {
+///     let cli = Cli::parse_from(["my-service", "--log-level", "debug", "--log-format", "json"]);
+///     let _guard = cli.logging.init()?;
+///     log::info!("Hello, world!");
+///     Ok(())
+/// }
+/// ```
+#[derive(clap::Args, Clone, Debug, PartialEq)]
+pub struct LoggingArgs {
+    /// The minimum severity of events that are logged, e.g. `info`, `warning` or `2`.
+    #[arg(long = "log-level", env = "LOG_LEVEL", value_enum, ignore_case = true, default_value_t = LogLevel::Info)]
+    pub level: LogLevel,
+    /// The output format of log events.
+    #[arg(long = "log-format", env = "LOG_FORMAT", value_enum, default_value_t = LogFormat::Compact)]
+    pub format: LogFormat,
+    /// `RUST_LOG`-style filter directives, e.g. `sqlx=warn,my_service=debug`.
+    #[arg(long = "log-filter", env = "LOG_FILTER")]
+    pub filter: Option<LogDirectives>,
+    /// Writes log events to this file, rotated daily, instead of stdout.
+    #[arg(long = "log-file", env = "LOG_FILE")]
+    pub file: Option<PathBuf>,
+    /// Disables ANSI colors. Any non-empty `NO_COLOR` value other than `false` or `0` also disables them.
+    #[arg(long = "no-color", env = "NO_COLOR", value_parser = clap::builder::FalseyValueParser::new())]
+    pub no_color: bool,
+}
+
+impl LoggingArgs {
+    /// Initializes the logger from the arguments.
+    ///
+    /// # Errors
+    /// Returns an error if the log file cannot be opened, or if a global subscriber
+    /// has already been set by other means.
+    pub fn init(self) -> anyhow::Result<LoggerGuard> {
+        new_tracer_logger_from_config(self.into())
+    }
+}
+
+impl From<LoggingArgs> for LoggerConfig {
+    fn from(args: LoggingArgs) -> Self {
+        LoggerConfig {
+            level: args.level,
+            directives: args.filter,
+            format: args.format,
+            sinks: args.file.map(|path| Sink::file(FileLogConfig::new(path))).into_iter().collect(),
+            ansi: !args.no_color,
+            ..LoggerConfig::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        logging: LoggingArgs,
+    }
+
+    /// Test parsing every argument from the command line.
+    #[test]
+    fn test_parse_args() {
+        let cli = Cli::try_parse_from([
+            "service",
+            "--log-level",
+            "debug",
+            "--log-format",
+            "json",
+            "--log-filter",
+            "sqlx=warn",
+            "--log-file",
+            "/tmp/service.log",
+            "--no-color",
+        ])
+        .unwrap();
+
+        let config = LoggerConfig::from(cli.logging);
+        assert_eq!(config.level, LogLevel::Debug);
+        assert_eq!(config.format, LogFormat::Json);
+        assert_eq!(config.directives, Some("sqlx=warn".parse().unwrap()));
+        assert_eq!(config.sinks, vec![Sink::file(FileLogConfig::new("/tmp/service.log"))]);
+        assert!(!config.ansi);
+    }
+
+    /// Test that malformed filter directives are rejected.
+    #[test]
+    fn test_parse_invalid_filter() {
+        assert!(Cli::try_parse_from(["service", "--log-filter", "sqlx=loud"]).is_err());
+    }
+
+    /// Test the defaults and the fallback to environment variables.
+    #[test]
+    fn test_env_fallback() {
+        let guard = EnvGuard::new(&[]);
+        let cli = Cli::try_parse_from(["service"]);
+        drop(guard);
+        assert_eq!(LoggerConfig::from(cli.unwrap().logging), LoggerConfig::default());
+
+        let guard = EnvGuard::new(&[("LOG_LEVEL", "warn"), ("LOG_FORMAT", "pretty"), ("NO_COLOR", "1")]);
+        let cli = Cli::try_parse_from(["service", "--log-format", "full"]);
+        drop(guard);
+
+        let args = cli.unwrap().logging;
+        assert_eq!(args.level, LogLevel::Warn);
+        assert_eq!(args.format, LogFormat::Full);
+        assert!(args.no_color);
+    }
+
+    /// Replaces the variables read by `LoggingArgs` with `vars` while it is alive and restores them when dropped.
+    ///
+    /// Guards are serialized by a lock, so tests changing these variables do not interfere.
+    struct EnvGuard {
+        saved: Vec<(&'static str, Option<std::ffi::OsString>)>,
+        _lock: std::sync::MutexGuard<'static, ()>,
+    }
+
+    impl EnvGuard {
+        const VARS: [&'static str; 5] = ["LOG_LEVEL", "LOG_FORMAT", "LOG_FILTER", "LOG_FILE", "NO_COLOR"];
+
+        fn new(vars: &[(&str, &str)]) -> Self {
+            static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+            let lock = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let saved = Self::VARS.iter().map(|name| (*name, std::env::var_os(name))).collect();
+            for name in Self::VARS {
+                std::env::remove_var(name);
+            }
+            for (name, value) in vars {
+                std::env::set_var(name, value);
+            }
+            EnvGuard { saved, _lock: lock }
+        }
+    }
+
+    impl Drop for EnvGuard {
+        fn drop(&mut self) {
+            for (name, value) in &self.saved {
+                match value {
+                    Some(value) => std::env::set_var(name, value),
+                    None => std::env::remove_var(name),
+                }
+            }
+        }
+    }
+}
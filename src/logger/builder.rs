@@ -0,0 +1,986 @@
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tracing_subscriber::fmt::format::{DefaultFields, FormatEvent, FormatFields, Format, Full, PrettyFields};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{fmt, Layer, Registry};
+
+use crate::logger::file::FileLogConfig;
+use crate::logger::guard::LoggerGuard;
+use crate::logger::handle::LoggerHandle;
+use crate::logger::journald::JournaldConfig;
+use crate::logger::otlp::OtlpConfig;
+use crate::logger::panic;
+use crate::logger::rate_limit::{RateLimitConfig, RateLimitLayer};
+use crate::logger::redact::{RedactingEvents, RedactingFields, RedactingJsonFields, RedactionConfig, Redactor};
+use crate::logger::sink::{Sink, SinkDestination};
+use crate::logger::span_timing::{SpanTimingConfig, SpanTimingLayer};
+use crate::logger::syslog::SyslogConfig;
+use crate::logger::time::{TimestampFormat, Timer};
+use crate::logger::tracer_logger::{LogDirectives, LogFormat, LogLevel};
+use crate::logger::writer::NonBlocking;
+
+/// A boxed layer that can be composed into the logger's subscriber.
+pub(crate) type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync + 'static>;
+
+/// The guard of the logger installed by this crate, if any.
+static INSTALLED: OnceLock<LoggerGuard> = OnceLock::new();
+
+/// Serializes installations, so that concurrent calls build and install a single logger.
+static INSTALL_LOCK: Mutex<()> = Mutex::new(());
+
+/// `LoggerBuilder` configures and installs the global tracing-based logger.
+///
+/// [`new_tracer_logger`](crate::logger::tracer_logger::new_tracer_logger) covers the
+/// common case of choosing only a `LogLevel`; the builder exposes the remaining
+/// options, such as the output format, per-target filter directives and the sinks
+/// events are written to. Without any sink, events are written to stdout.
+///
+/// # Example
+/// ```rust
+/// use multitool_hg::logger::builder::LoggerBuilder;
+/// use multitool_hg::logger::tracer_logger::{LogFormat, LogLevel};
+///
+/// fn main() {
+///     let _guard = LoggerBuilder::new()
+///         .level(LogLevel::Debug)
+///         .directives("sqlx=warn,hyper=info".parse().unwrap())
+///         .format(LogFormat::Json)
+///         .init();
+///     log::info!("Hello, world!");
+/// }
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct LoggerBuilder {
+    level: LogLevel,
+    directives: Option<LogDirectives>,
+    format: LogFormat,
+    sinks: Vec<Sink>,
+    otlp: Option<OtlpConfig>,
+    redaction: Option<RedactionConfig>,
+    rate_limit: Option<RateLimitConfig>,
+    span_timing: Option<SpanTimingConfig>,
+    panic_hook: bool,
+    options: FormatOptions,
+}
+
+/// The fields included in formatted log events, and how their values are masked.
+#[derive(Clone, Debug)]
+pub(crate) struct FormatOptions {
+    pub(crate) ansi: bool,
+    pub(crate) timestamps: bool,
+    pub(crate) timestamp_format: TimestampFormat,
+    pub(crate) thread_ids: bool,
+    pub(crate) thread_names: bool,
+    pub(crate) targets: bool,
+    pub(crate) source_location: bool,
+    pub(crate) redactor: Option<Arc<Redactor>>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            ansi: true,
+            timestamps: true,
+            timestamp_format: TimestampFormat::default(),
+            thread_ids: true,
+            thread_names: true,
+            targets: true,
+            source_location: false,
+            redactor: None,
+        }
+    }
+}
+
+impl LoggerBuilder {
+    /// Creates a new `LoggerBuilder` with the `Info` level and the `Compact` format.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the default maximum level of events that are logged.
+    pub fn level(mut self, level: LogLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets default `RUST_LOG`-style filter directives that are layered on top of the level,
+    /// e.g. `my_service=debug,sqlx=warn`.
+    pub fn directives(mut self, directives: LogDirectives) -> Self {
+        self.directives = Some(directives);
+        self
+    }
+
+    /// Sets the default output format of log events.
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets whether ANSI colors are used on stdout and stderr (default is true).
+    /// File output never contains ANSI color codes.
+    pub fn ansi(mut self, ansi: bool) -> Self {
+        self.options.ansi = ansi;
+        self
+    }
+
+    /// Sets whether events include a timestamp (default is true).
+    pub fn timestamps(mut self, timestamps: bool) -> Self {
+        self.options.timestamps = timestamps;
+        self
+    }
+
+    /// Sets how timestamps are rendered (default is RFC 3339 in UTC).
+    ///
+    /// An invalid custom format makes initialization fail.
+    pub fn timestamp_format(mut self, timestamp_format: TimestampFormat) -> Self {
+        self.options.timestamp_format = timestamp_format;
+        self
+    }
+
+    /// Sets whether events include the ID of the thread that emitted them (default is true).
+    pub fn thread_ids(mut self, thread_ids: bool) -> Self {
+        self.options.thread_ids = thread_ids;
+        self
+    }
+
+    /// Sets whether events include the name of the thread that emitted them (default is true).
+    pub fn thread_names(mut self, thread_names: bool) -> Self {
+        self.options.thread_names = thread_names;
+        self
+    }
+
+    /// Sets whether events include their target, usually the module path (default is true).
+    pub fn targets(mut self, targets: bool) -> Self {
+        self.options.targets = targets;
+        self
+    }
+
+    /// Sets whether events include the source file and line that emitted them (default is false).
+    pub fn source_location(mut self, source_location: bool) -> Self {
+        self.options.source_location = source_location;
+        self
+    }
+
+    /// Masks sensitive fields and values before any sink writes them.
+    ///
+    /// Spans exported over OTLP are not redacted.
+    pub fn redact(mut self, redaction: RedactionConfig) -> Self {
+        self.redaction = Some(redaction);
+        self
+    }
+
+    /// Limits the number of events per callsite and samples debug and trace events.
+    pub fn rate_limit(mut self, rate_limit: RateLimitConfig) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Measures the busy and idle time of spans and warns about spans that exceed
+    /// the configured thresholds.
+    ///
+    /// Only spans enabled by the logger's default level and directives are measured.
+    pub fn span_timing(mut self, span_timing: SpanTimingConfig) -> Self {
+        self.span_timing = Some(span_timing);
+        self
+    }
+
+    /// Sets whether panics are logged as `error` events, with the message, location,
+    /// thread name and backtrace as fields (default is false).
+    ///
+    /// The logger's sinks are flushed after the event is logged, so that the panic is
+    /// recorded even if the process aborts. The previously installed panic hook runs
+    /// afterwards, so panics are still reported by it as well. The hook is only installed by
+    /// [`LoggerBuilder::init`] and [`LoggerBuilder::try_init`].
+    pub fn panic_hook(mut self, panic_hook: bool) -> Self {
+        self.panic_hook = panic_hook;
+        self
+    }
+
+    /// Adds a sink that log events are written to.
+    ///
+    /// Once at least one sink has been added, events are no longer written to stdout
+    /// unless a stdout sink is added explicitly.
+    pub fn sink(mut self, sink: Sink) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Writes log events to a rotating file, using the default format and filtering.
+    ///
+    /// This is a shorthand for `.sink(Sink::file(file))`. File output never contains
+    /// ANSI color codes.
+    pub fn file(self, file: FileLogConfig) -> Self {
+        self.sink(Sink::file(file))
+    }
+
+    /// Exports spans to an OpenTelemetry collector over OTLP, in addition to the sinks.
+    ///
+    /// Requires the `logger-otlp` feature; otherwise initialization fails.
+    pub fn otlp(mut self, otlp: OtlpConfig) -> Self {
+        self.otlp = Some(otlp);
+        self
+    }
+
+    /// Installs the configured logger as the global default subscriber.
+    ///
+    /// Records emitted through the `log` crate (`log::info!` and friends, including from
+    /// dependencies) are forwarded to the logger and filtered like any other event.
+    ///
+    /// Returns a [`LoggerGuard`] that flushes the logger's writers when dropped and gives
+    /// access to the [`LoggerHandle`] for changing the level and directives at runtime.
+    ///
+    /// If a logger has already been installed by this crate, the call is a no-op and the
+    /// returned guard refers to the existing logger; the new settings are ignored.
+    ///
+    /// # Panics
+    /// Panics if a global subscriber has already been set by other means.
+    /// Use [`LoggerBuilder::try_init`] to handle that case.
+    pub fn init(self) -> LoggerGuard {
+        self.try_init().expect("failed to initialize the logger")
+    }
+
+    /// Installs the configured logger as the global default subscriber, returning an
+    /// error instead of panicking if that is not possible.
+    ///
+    /// Like [`LoggerBuilder::init`], repeated calls are a no-op once a logger has been
+    /// installed by this crate, which makes it safe to call from every test.
+    ///
+    /// # Errors
+    /// Returns an error if a global subscriber has already been set by other means,
+    /// if a log file cannot be opened, or if the OTLP exporter cannot be created.
+    pub fn try_init(self) -> anyhow::Result<LoggerGuard> {
+        if let Some(guard) = INSTALLED.get() {
+            return Ok(guard.clone());
+        }
+        // Checked again under the lock, so that a concurrent call neither builds sinks it
+        // would not use nor fails while the winner is still installing its logger.
+        let _lock = INSTALL_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(guard) = INSTALLED.get() {
+            return Ok(guard.clone());
+        }
+
+        let panic_hook = self.panic_hook;
+        let (subscriber, guard) = self.build()?;
+        tracing::subscriber::set_global_default(subscriber)
+            .map_err(|err| anyhow::Error::new(err).context("a global tracing subscriber has already been set"))?;
+
+        install_log_bridge();
+        // The stored guard lives until the end of the process, so it must not keep the
+        // OTLP exporter from being shut down when the caller drops its guard.
+        let _ = INSTALLED.set(guard.detached());
+        if panic_hook {
+            panic::install(guard.detached());
+        }
+        Ok(guard)
+    }
+
+    /// Builds the subscriber without installing it, opening the configured sinks.
+    pub(crate) fn build(mut self) -> anyhow::Result<(impl tracing::Subscriber + Send + Sync, LoggerGuard)> {
+        self.options.timestamp_format.check()?;
+        if let Some(redaction) = &self.redaction {
+            self.options.redactor = Some(Arc::new(redaction.redactor()?));
+        }
+        let handle = LoggerHandle::new(self.level, self.directives);
+        let sinks = if self.sinks.is_empty() {
+            vec![Sink::stdout()]
+        } else {
+            self.sinks
+        };
+
+        let mut layers: Vec<BoxedLayer<Registry>> = Vec::with_capacity(sinks.len() + 1);
+        let mut hooks: Vec<BoxedLayer<Registry>> = Vec::new();
+        if let Some(rate_limit) = self.rate_limit {
+            let layer = RateLimitLayer::new(rate_limit)?;
+            hooks.push(layer.clone().boxed());
+            // Must come first, as it decides whether the following layers see an event.
+            layers.push(layer.boxed());
+        }
+        let mut writers = Vec::new();
+        for sink in sinks {
+            let format = sink.format.unwrap_or(self.format);
+            let options = self.options.clone();
+            let layer = match &sink.destination {
+                SinkDestination::Stdout => fmt_layer(format, std::io::stdout, options),
+                SinkDestination::Stderr => fmt_layer(format, std::io::stderr, options),
+                SinkDestination::File(file) => {
+                    let writer = file.open()?;
+                    writers.push(writer.clone());
+                    // File output never contains ANSI color codes.
+                    fmt_layer(format, writer, FormatOptions { ansi: false, ..options })
+                }
+                SinkDestination::Journald(journald) => journald_layer(journald, options.redactor)?,
+                SinkDestination::Syslog(syslog) => {
+                    let (layer, writer) = syslog_layer(syslog, options.redactor)?;
+                    writers.push(writer);
+                    layer
+                }
+            };
+            let filter = handle.sink_filter(sink.level, sink.directives);
+            layers.push(layer.with_filter(filter).boxed());
+        }
+
+        if let Some(span_timing) = self.span_timing {
+            let layer = SpanTimingLayer::new(span_timing)?;
+            hooks.push(layer.clone().boxed());
+            layers.push(layer.with_filter(handle.sink_filter(None, None)).boxed());
+        }
+
+        let guard = LoggerGuard::new(handle.clone(), writers);
+        let guard = match &self.otlp {
+            Some(otlp) => {
+                let (layer, guard) = otlp_layer(otlp, guard)?;
+                layers.push(layer.with_filter(handle.sink_filter(None, None)).boxed());
+                guard
+            }
+            None => guard,
+        };
+
+        let subscriber = tracing_subscriber::registry().with(layers).with(RegisterDispatch(hooks));
+        Ok((subscriber, guard))
+    }
+
+    /// Builds the subscriber without installing it, writing events to `writer` only.
+    #[cfg(test)]
+    pub(crate) fn build_with_writer<W>(self, writer: W, ansi: bool) -> (impl tracing::Subscriber + Send + Sync, LoggerHandle)
+    where
+        W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+    {
+        let handle = LoggerHandle::new(self.level, self.directives);
+        let options = FormatOptions {
+            ansi: ansi && self.options.ansi,
+            redactor: self.redaction.map(|redaction| Arc::new(redaction.redactor().unwrap())),
+            ..self.options
+        };
+        let layer = fmt_layer(self.format, writer, options).with_filter(handle.sink_filter(None, None));
+        (tracing_subscriber::registry().with(layer), handle)
+    }
+}
+
+/// Forwards the registration of the dispatcher to clones of the layers in the
+/// logger's layer list, since `Vec<L>` does not forward it to its elements.
+struct RegisterDispatch(Vec<BoxedLayer<Registry>>);
+
+impl<S: tracing::Subscriber> Layer<S> for RegisterDispatch {
+    fn on_register_dispatch(&self, dispatch: &tracing::Dispatch) {
+        for layer in &self.0 {
+            layer.on_register_dispatch(dispatch);
+        }
+    }
+}
+
+/// Forwards records of the `log` crate to `tracing`.
+///
+/// If another `log` backend is already installed, it is left in place and a warning is logged.
+fn install_log_bridge() {
+    if tracing_log::LogTracer::init().is_err() {
+        tracing::warn!("another `log` backend is already installed; `log` records will not reach this logger");
+    }
+    sync_log_max_level();
+}
+
+/// Aligns the `log` crate's static maximum level with the most verbose level any sink
+/// currently accepts, so that disabled `log` records are skipped cheaply.
+pub(crate) fn sync_log_max_level() {
+    log::set_max_level(tracing_log::AsLog::as_log(&tracing::level_filters::LevelFilter::current()));
+}
+
+/// Builds the layer exporting spans over OTLP and attaches its tracer provider to `guard`.
+#[cfg(feature = "logger-otlp")]
+fn otlp_layer(otlp: &OtlpConfig, guard: LoggerGuard) -> anyhow::Result<(BoxedLayer<Registry>, LoggerGuard)> {
+    use opentelemetry::trace::TracerProvider;
+
+    let tracer_provider = otlp.tracer_provider()?;
+    let tracer = tracer_provider.tracer("multitool-hg");
+    let layer = tracing_opentelemetry::layer().with_tracer(tracer).boxed();
+    Ok((layer, guard.with_tracer_provider(tracer_provider)))
+}
+
+/// Fails because OTLP export is not compiled in.
+#[cfg(not(feature = "logger-otlp"))]
+fn otlp_layer(_otlp: &OtlpConfig, _guard: LoggerGuard) -> anyhow::Result<(BoxedLayer<Registry>, LoggerGuard)> {
+    Err(anyhow::Error::msg("Exporting spans over OTLP requires the `logger-otlp` feature."))
+}
+
+/// Builds the layer sending events to journald, masking values with `redactor` first.
+#[cfg(feature = "logger-journald")]
+fn journald_layer(journald: &JournaldConfig, redactor: Option<Arc<Redactor>>) -> anyhow::Result<BoxedLayer<Registry>> {
+    let layer = journald.layer()?;
+    Ok(match redactor {
+        Some(redactor) => crate::logger::redact::RedactingLayer::new(layer, redactor).boxed(),
+        None => layer.boxed(),
+    })
+}
+
+/// Fails because journald output is not compiled in.
+#[cfg(not(feature = "logger-journald"))]
+fn journald_layer(_journald: &JournaldConfig, _redactor: Option<Arc<Redactor>>) -> anyhow::Result<BoxedLayer<Registry>> {
+    Err(anyhow::Error::msg("Writing logs to journald requires the `logger-journald` feature."))
+}
+
+/// Builds the layer sending events to syslog, and returns the writer that must be flushed on shutdown.
+#[cfg(feature = "logger-syslog")]
+fn syslog_layer(syslog: &SyslogConfig, redactor: Option<Arc<Redactor>>) -> anyhow::Result<(BoxedLayer<Registry>, NonBlocking)> {
+    let (layer, writer) = syslog.open()?;
+    Ok((layer.with_redactor(redactor).boxed(), writer))
+}
+
+/// Fails because syslog output is not compiled in.
+#[cfg(not(feature = "logger-syslog"))]
+fn syslog_layer(_syslog: &SyslogConfig, _redactor: Option<Arc<Redactor>>) -> anyhow::Result<(BoxedLayer<Registry>, NonBlocking)> {
+    Err(anyhow::Error::msg("Writing logs to syslog requires the `logger-syslog` feature."))
+}
+
+/// Builds the formatting layer for `format`, writing events to `writer`.
+///
+/// Every format includes levels; timestamps, targets, thread IDs, thread names,
+/// source locations and ANSI colors are included as set in `options`.
+pub(crate) fn fmt_layer<S, W>(format: LogFormat, writer: W, options: FormatOptions) -> BoxedLayer<S>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer()
+        .with_writer(writer)
+        .with_ansi(options.ansi)
+        .with_level(true) // include levels in formatted output
+        .with_target(options.targets)
+        .with_thread_ids(options.thread_ids)
+        .with_thread_names(options.thread_names)
+        .with_file(options.source_location)
+        .with_line_number(options.source_location);
+
+    if options.timestamps {
+        format_layer(format, layer.with_timer(Timer::new(options.timestamp_format)), options.redactor)
+    } else {
+        format_layer(format, layer.without_time(), options.redactor)
+    }
+}
+
+/// Applies `format` to a layer whose timer has already been chosen.
+fn format_layer<S, W, T>(
+    format: LogFormat,
+    layer: fmt::Layer<S, DefaultFields, Format<Full, T>, W>,
+    redactor: Option<Arc<Redactor>>,
+) -> BoxedLayer<S>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+    T: fmt::time::FormatTime + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Compact => redacted(layer.compact(), redactor, |redactor| RedactingFields::new(DefaultFields::new(), redactor)),
+        LogFormat::Pretty => redacted(layer.pretty(), redactor, |redactor| RedactingFields::new(PrettyFields::new(), redactor)),
+        LogFormat::Full => redacted(layer, redactor, |redactor| RedactingFields::new(DefaultFields::new(), redactor)),
+        LogFormat::Json => redacted(
+            layer
+                .json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .with_span_list(true),
+            redactor,
+            RedactingJsonFields::new,
+        ),
+    }
+}
+
+/// Masks the values of events and spans with `redactor`, if any, before `layer` formats them.
+fn redacted<S, N, N2, E, W>(
+    layer: fmt::Layer<S, N, E, W>,
+    redactor: Option<Arc<Redactor>>,
+    fields: impl FnOnce(Arc<Redactor>) -> N2,
+) -> BoxedLayer<S>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'w> FormatFields<'w> + Send + Sync + 'static,
+    N2: for<'w> FormatFields<'w> + Send + Sync + 'static,
+    E: FormatEvent<S, N> + FormatEvent<S, N2> + Send + Sync + 'static,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match redactor {
+        None => layer.boxed(),
+        Some(redactor) => layer
+            .map_fmt_fields(|_| fields(redactor.clone()))
+            .map_event_format(|format| RedactingEvents::new(format, redactor))
+            .boxed(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use crate::logger::tracer_logger::env_filter;
+    use super::*;
+
+    /// Returns format options without ANSI colors.
+    pub(crate) fn plain() -> FormatOptions {
+        FormatOptions {
+            ansi: false,
+            ..FormatOptions::default()
+        }
+    }
+
+    /// A `MakeWriter` that collects everything written to it in memory.
+    #[derive(Clone, Default)]
+    pub(crate) struct TestWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl TestWriter {
+        pub(crate) fn output(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for TestWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for TestWriter {
+        type Writer = TestWriter;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// Test that the JSON format flattens event fields and includes span context.
+    #[test]
+    fn test_json_format() {
+        let writer = TestWriter::default();
+        let subscriber = tracing_subscriber::registry().with(fmt_layer(LogFormat::Json, writer.clone(), plain()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", request_id = 7);
+            let _entered = span.enter();
+            tracing::info!(user_id = 5, "user logged in");
+        });
+
+        let line: serde_json::Value = serde_json::from_str(writer.output().trim()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["message"], "user logged in");
+        assert_eq!(line["user_id"], 5);
+        assert_eq!(line["span"]["name"], "request");
+        assert_eq!(line["span"]["request_id"], 7);
+        assert_eq!(line["spans"][0]["name"], "request");
+    }
+
+    /// Test that directives filter events per target.
+    #[test]
+    fn test_directives_filter_targets() {
+        let writer = TestWriter::default();
+        let directives = "noisy=warn,my_service=debug".parse().unwrap();
+        let filter = env_filter(&LogLevel::Info, Some(&directives));
+        let subscriber = tracing_subscriber::registry()
+            .with(fmt_layer(LogFormat::Compact, writer.clone(), plain()).with_filter(filter));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "noisy", "noisy info");
+            tracing::warn!(target: "noisy", "noisy warning");
+            tracing::debug!(target: "my_service", "service debug");
+            tracing::debug!(target: "other", "other debug");
+            tracing::info!(target: "other", "other info");
+        });
+
+        let output = writer.output();
+        assert!(!output.contains("noisy info"));
+        assert!(output.contains("noisy warning"));
+        assert!(output.contains("service debug"));
+        assert!(!output.contains("other debug"));
+        assert!(output.contains("other info"));
+    }
+
+    /// Test that the handle changes the level and directives of a running logger.
+    #[test]
+    fn test_handle_reloads_filter() {
+        let writer = TestWriter::default();
+        let (subscriber, handle) = LoggerBuilder::new().level(LogLevel::Info).build_with_writer(writer.clone(), false);
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("hidden debug");
+
+            handle.set_level(LogLevel::Debug).unwrap();
+            tracing::debug!("visible debug");
+
+            handle.set_directives("noisy=error").unwrap();
+            tracing::warn!(target: "noisy", "hidden warning");
+            tracing::debug!("still visible debug");
+
+            assert!(handle.set_directives("noisy=loud").is_err());
+            assert_eq!(handle.directives().unwrap().to_string(), "noisy=error");
+
+            handle.set_directives("").unwrap();
+            tracing::warn!(target: "noisy", "visible warning");
+        });
+
+        let output = writer.output();
+        assert!(!output.contains("hidden debug"));
+        assert!(output.contains("visible debug"));
+        assert!(!output.contains("hidden warning"));
+        assert!(output.contains("still visible debug"));
+        assert!(output.contains("visible warning"));
+        assert_eq!(handle.level(), LogLevel::Debug);
+        assert_eq!(handle.directives(), None);
+    }
+
+    /// Test that repeated and concurrent initializations are a no-op that returns the same logger.
+    #[test]
+    fn test_try_init_is_idempotent() {
+        let barrier = Arc::new(std::sync::Barrier::new(8));
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    LoggerBuilder::new().level(LogLevel::Error).try_init()
+                })
+            })
+            .collect();
+        for thread in threads {
+            assert!(thread.join().unwrap().is_ok());
+        }
+
+        let first = LoggerBuilder::new().level(LogLevel::Error).try_init().unwrap();
+        let second = LoggerBuilder::new().level(LogLevel::Trace).try_init().unwrap();
+        assert_eq!(second.handle().level(), LogLevel::Error);
+        drop(first);
+
+        // The old entry point must not panic either.
+        crate::logger::tracer_logger::new_tracer_logger(LogLevel::Debug);
+        assert_eq!(second.handle().level(), LogLevel::Error);
+    }
+
+    /// Test that events are written to the configured log file without ANSI colors.
+    #[test]
+    fn test_file_output() {
+        let dir = std::env::temp_dir().join(format!("multitool-file-output-{}", std::process::id()));
+        let path = dir.join("service.log");
+        let (subscriber, guard) = LoggerBuilder::new()
+            .file(FileLogConfig::new(&path))
+            .build()
+            .unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(user_id = 5, "written to disk");
+        });
+        drop(guard);
+
+        let output = std::fs::read_to_string(&path).unwrap();
+        assert!(output.contains("written to disk"), "{}", output);
+        assert!(!output.contains('\x1b'), "{}", output);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Test that sinks have independent formats and levels, and that only sinks
+    /// without their own level follow runtime level changes.
+    #[test]
+    fn test_multiple_sinks() {
+        let dir = std::env::temp_dir().join(format!("multitool-multiple-sinks-{}", std::process::id()));
+        let json_path = dir.join("debug.json");
+        let text_path = dir.join("warn.log");
+        let (subscriber, guard) = LoggerBuilder::new()
+            .level(LogLevel::Warn)
+            .sink(Sink::file(FileLogConfig::new(&json_path)).format(LogFormat::Json).level(LogLevel::Debug))
+            .sink(Sink::file(FileLogConfig::new(&text_path)))
+            .build()
+            .unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("debug event");
+            tracing::warn!("warn event");
+            guard.handle().set_level(LogLevel::Error).unwrap();
+            tracing::warn!("second warn event");
+        });
+        drop(guard);
+
+        let json = std::fs::read_to_string(&json_path).unwrap();
+        let lines: Vec<serde_json::Value> = json.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let messages: Vec<&str> = lines.iter().map(|line| line["message"].as_str().unwrap()).collect();
+        assert_eq!(messages, vec!["debug event", "warn event", "second warn event"]);
+
+        let text = std::fs::read_to_string(&text_path).unwrap();
+        assert!(!text.contains("debug event"));
+        assert!(text.contains("warn event"));
+        assert!(!text.contains("second warn event"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Test that the human-readable formats include the message and fields.
+    #[test]
+    fn test_text_formats() {
+        for format in [LogFormat::Compact, LogFormat::Pretty, LogFormat::Full] {
+            let writer = TestWriter::default();
+            let subscriber = tracing_subscriber::registry().with(fmt_layer(format, writer.clone(), plain()));
+
+            tracing::subscriber::with_default(subscriber, || {
+                tracing::warn!(user_id = 5, "disk almost full");
+            });
+
+            let output = writer.output();
+            assert!(output.contains("WARN"), "{}: {}", format, output);
+            assert!(output.contains("disk almost full"), "{}: {}", format, output);
+            assert!(output.contains("user_id"), "{}: {}", format, output);
+        }
+    }
+
+    /// Test that spans are exported to an OTLP/HTTP collector when the guard is dropped.
+    #[cfg(feature = "logger-otlp")]
+    #[test]
+    fn test_otlp_http_export() {
+        use std::io::{BufRead, BufReader, Read};
+        use std::net::TcpListener;
+        use crate::logger::otlp::OtlpProtocol;
+
+        // A minimal stand-in for the collector: accepts one request and answers 200 OK.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let collector = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                if line.trim().is_empty() {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\ncontent-type: application/x-protobuf\r\n\r\n")
+                .unwrap();
+            (head, body)
+        });
+
+        let otlp = OtlpConfig {
+            protocol: OtlpProtocol::HttpProtobuf,
+            resource_attributes: [("deployment.environment".to_string(), "test".to_string())].into(),
+            ..OtlpConfig::new(endpoint, "otlp-test-service")
+        };
+        let (subscriber, guard) = LoggerBuilder::new()
+            .sink(Sink::new(SinkDestination::Stderr).level(LogLevel::Error))
+            .otlp(otlp)
+            .build()
+            .unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("checkout_order", order_id = 7);
+            let _entered = span.enter();
+            tracing::info!("order placed");
+        });
+        drop(guard);
+
+        let (head, body) = collector.join().unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(head.starts_with("POST /v1/traces "), "{}", head);
+        assert!(body.contains("otlp-test-service"));
+        assert!(body.contains("deployment.environment"));
+        assert!(body.contains("checkout_order"));
+    }
+
+    /// Test that spans are exported to an OTLP/gRPC collector from within a Tokio runtime.
+    #[cfg(feature = "logger-otlp")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_otlp_grpc_export() {
+        use tokio::io::AsyncReadExt;
+        use tokio::net::TcpListener;
+
+        // A stand-in for the collector: reads the HTTP/2 stream until the span arrives,
+        // then closes the connection, which fails the export without waiting for a timeout.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let collector = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            let mut buffer = [0; 4096];
+            while !String::from_utf8_lossy(&received).contains("checkout_order") {
+                match stream.read(&mut buffer).await.unwrap() {
+                    0 => break,
+                    n => received.extend_from_slice(&buffer[..n]),
+                }
+            }
+            received
+        });
+
+        // Errors of the exporter once the collector hangs up go to a file instead of the test's output.
+        let path = std::env::temp_dir().join(format!("multitool-otlp-grpc-{}.log", std::process::id()));
+        let (subscriber, guard) = LoggerBuilder::new()
+            .sink(Sink::file(FileLogConfig::new(&path)).level(LogLevel::Error))
+            .otlp(OtlpConfig::new(endpoint, "otlp-test-service"))
+            .build()
+            .unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("checkout_order", order_id = 7);
+            let _entered = span.enter();
+            tracing::info!("order placed");
+        });
+        tokio::task::spawn_blocking(move || drop(guard)).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let received = String::from_utf8_lossy(&collector.await.unwrap()).into_owned();
+        assert!(received.starts_with("PRI * HTTP/2.0"), "{:?}", received);
+        assert!(received.contains("otlp-test-service"), "{:?}", received);
+        assert!(received.contains("checkout_order"), "{:?}", received);
+    }
+
+    /// Test that OTLP/gRPC export fails instead of panicking outside a Tokio runtime.
+    #[cfg(feature = "logger-otlp")]
+    #[test]
+    fn test_otlp_grpc_requires_runtime() {
+        let err = LoggerBuilder::new()
+            .otlp(OtlpConfig::new("http://localhost:4317", "my-service"))
+            .build()
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Tokio runtime"), "{}", err);
+    }
+
+    /// Test that OTLP export fails clearly when the feature is not compiled in.
+    #[cfg(not(feature = "logger-otlp"))]
+    #[test]
+    fn test_otlp_requires_feature() {
+        let result = LoggerBuilder::new().otlp(OtlpConfig::new("http://localhost:4317", "my-service")).build();
+        assert!(result.is_err());
+    }
+
+    /// Test that journald and syslog sinks fail clearly when their features are not compiled in.
+    #[cfg(not(any(feature = "logger-journald", feature = "logger-syslog")))]
+    #[test]
+    fn test_system_sinks_require_features() {
+        use crate::logger::journald::JournaldConfig;
+        use crate::logger::syslog::SyslogConfig;
+
+        let err = LoggerBuilder::new().sink(Sink::journald(JournaldConfig::default())).build().err().unwrap();
+        assert!(err.to_string().contains("logger-journald"), "{}", err);
+        let err = LoggerBuilder::new().sink(Sink::syslog(SyslogConfig::default())).build().err().unwrap();
+        assert!(err.to_string().contains("logger-syslog"), "{}", err);
+    }
+
+    /// Test that the rate limit reporter runs when the layer is part of the logger.
+    #[test]
+    fn test_rate_limit_reporter() {
+        let path = std::env::temp_dir().join(format!("multitool-rate-limit-{}.log", std::process::id()));
+        let (subscriber, guard) = LoggerBuilder::new()
+            .file(FileLogConfig::new(&path))
+            .rate_limit(RateLimitConfig {
+                max_events: 1,
+                interval: Duration::from_millis(50),
+                ..RateLimitConfig::default()
+            })
+            .build()
+            .unwrap();
+
+        let dispatch = tracing::Dispatch::new(subscriber);
+        tracing::dispatcher::with_default(&dispatch, || {
+            for _ in 0..3 {
+                tracing::error!("hot path");
+            }
+        });
+        // The reporter runs every interval; wait for its warning with a generous deadline.
+        let deadline = std::time::Instant::now() + Duration::from_secs(30);
+        let mut output = String::new();
+        while !output.contains("suppressed 2 events") && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            guard.flush();
+            output = std::fs::read_to_string(&path).unwrap_or_default();
+        }
+        drop(guard);
+
+        assert!(output.contains("suppressed 2 events"), "{}", output);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// Test that slow spans enabled by the logger's level are reported to the sinks.
+    #[test]
+    fn test_span_timing() {
+        let path = std::env::temp_dir().join(format!("multitool-span-timing-{}.log", std::process::id()));
+        let (subscriber, guard) = LoggerBuilder::new()
+            .file(FileLogConfig::new(&path))
+            .span_timing(SpanTimingConfig::default().default_threshold(Duration::from_millis(10)))
+            .build()
+            .unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("checkout").in_scope(|| std::thread::sleep(Duration::from_millis(20)));
+            tracing::trace_span!("hidden").in_scope(|| std::thread::sleep(Duration::from_millis(20)));
+        });
+        drop(guard);
+
+        let output = std::fs::read_to_string(&path).unwrap();
+        assert!(output.contains("slow span checkout took"), "{}", output);
+        assert!(!output.contains("hidden"), "{}", output);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// Test that records of the `log` crate reach the logger and respect its level.
+    ///
+    /// `log`'s maximum level is global and other tests change it concurrently, so the
+    /// test runs in a child process where only its own changes apply.
+    #[test]
+    fn test_log_records_are_captured() {
+        const CHILD: &str = "MULTITOOL_LOG_RECORDS_CHILD";
+        if std::env::var_os(CHILD).is_none() {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["logger::builder::tests::test_log_records_are_captured", "--exact", "--test-threads=1"])
+                .env(CHILD, "1")
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(output.status.success(), "{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+            assert!(stdout.contains("1 passed"), "{}", stdout);
+            return;
+        }
+
+        let _ = tracing_log::LogTracer::init();
+        let writer = TestWriter::default();
+        let (subscriber, handle) = LoggerBuilder::new().level(LogLevel::Info).build_with_writer(writer.clone(), false);
+
+        tracing::subscriber::with_default(subscriber, || {
+            sync_log_max_level();
+            assert_eq!(log::max_level(), log::LevelFilter::Info);
+            log::info!(target: "legacy_crate", "info from log");
+            log::debug!(target: "legacy_crate", "debug from log");
+            handle.set_level(LogLevel::Debug).unwrap();
+            assert_eq!(log::max_level(), log::LevelFilter::Debug);
+            log::debug!(target: "legacy_crate", "second debug from log");
+        });
+
+        let output = writer.output();
+        assert!(output.contains("info from log"), "{}", output);
+        assert!(output.contains("legacy_crate"), "{}", output);
+        assert!(output.contains("second debug from log"), "{}", output);
+        assert_eq!(output.lines().count(), 2, "{}", output);
+    }
+
+    /// Test that `log::info!` is forwarded once the logger is installed globally.
+    #[test]
+    fn test_log_macros_after_init() {
+        let _guard = LoggerBuilder::new().level(LogLevel::Error).try_init().unwrap();
+        let writer = TestWriter::default();
+        let (subscriber, _handle) = LoggerBuilder::new().level(LogLevel::Info).build_with_writer(writer.clone(), false);
+
+        tracing::subscriber::with_default(subscriber, || {
+            // The global logger only needs `error`, but this scoped one accepts `info`.
+            sync_log_max_level();
+            log::info!("logged through the log macros");
+        });
+
+        assert!(writer.output().contains("logged through the log macros"), "{}", writer.output());
+    }
+}
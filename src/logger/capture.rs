@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tracing::field::{Field, Visit};
+use tracing::subscriber::DefaultGuard;
+use tracing::{Event, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+use crate::logger::tracer_logger::LogLevel;
+
+/// A log event recorded by [`LogCapture`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapturedEvent {
+    /// The level of the event.
+    pub level: LogLevel,
+    /// The target of the event, usually the module path that emitted it.
+    pub target: String,
+    /// The formatted message of the event.
+    pub message: String,
+    /// The other fields of the event, formatted as strings. String values are stored
+    /// without quotes, and other values with their `Debug` representation.
+    pub fields: BTreeMap<String, String>,
+}
+
+impl CapturedEvent {
+    /// Returns the value of the field `name`, if the event has it.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str)
+    }
+}
+
+/// `LogCapture` records log events in memory so that tests can assert on them.
+///
+/// [`LogCapture::start`] installs a subscriber that captures events of every level
+/// emitted on the current thread, until the returned guard is dropped. It does not
+/// set the global subscriber and does not write anything to stdout, so tests using it
+/// can run in parallel. Records of the `log` crate are captured as well, provided
+/// their level is enabled by `log::max_level()`.
+///
+/// To capture `log` records, the first [`LogCapture::start`] installs
+/// `tracing_log::LogTracer` as the global `log` logger, unless another one is already
+/// installed. This lasts for the rest of the process, as `log` loggers cannot be removed:
+/// the logger's own `try_init` still works afterwards, since it keeps an installed bridge,
+/// but a later `env_logger::init()` in the same test binary fails.
+///
+/// # Example
+/// ```rust
+/// use multitool_hg::logger::capture::LogCapture;
+/// use multitool_hg::logger::tracer_logger::LogLevel;
+///
+/// let capture = LogCapture::new();
+/// {
+///     let _guard = capture.start();
+///     tracing::warn!(user_id = 5, "quota exceeded");
+/// }
+///
+/// assert!(capture.contains(LogLevel::Warn, "quota exceeded"));
+/// let event = &capture.events()[0];
+/// assert_eq!(event.field("user_id"), Some("5"));
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct LogCapture {
+    events: Arc<Mutex<Vec<CapturedEvent>>>,
+}
+
+impl LogCapture {
+    /// Creates an empty capture.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts capturing the events emitted on the current thread until the returned guard is dropped.
+    ///
+    /// Installs the global `log` bridge on first use; see the type documentation.
+    #[must_use = "events are only captured while the guard is alive"]
+    pub fn start(&self) -> DefaultGuard {
+        // Forward `log` records to `tracing`, unless another `log` backend is installed.
+        let _ = tracing_log::LogTracer::init();
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(self.clone()))
+    }
+
+    /// Runs `f` while capturing its events, and returns its result.
+    pub fn capture<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _guard = self.start();
+        f()
+    }
+
+    /// Returns the events captured so far, oldest first.
+    pub fn events(&self) -> Vec<CapturedEvent> {
+        self.lock().clone()
+    }
+
+    /// Returns whether an event at `level` whose message contains `message` has been captured.
+    pub fn contains(&self, level: LogLevel, message: &str) -> bool {
+        self.lock()
+            .iter()
+            .any(|event| event.level == level && event.message.contains(message))
+    }
+
+    /// Discards the events captured so far.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<CapturedEvent>> {
+        self.events.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogCapture {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        self.lock().push(CapturedEvent {
+            level: LogLevel::from(*metadata.level()),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        });
+    }
+}
+
+/// Collects the message and fields of an event, skipping the fields added by the `log` bridge.
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl FieldVisitor {
+    fn insert(&mut self, field: &Field, value: String) {
+        match field.name() {
+            "message" => self.message = value,
+            name if name.starts_with("log.") => {}
+            name => {
+                self.fields.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{:?}", value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that levels, targets, messages and fields are captured.
+    #[test]
+    fn test_capture_events() {
+        let capture = LogCapture::new();
+        capture.capture(|| {
+            tracing::warn!(user_id = 5, name = "alice", ok = true, "quota exceeded");
+            tracing::debug!(target: "custom_target", "details");
+        });
+
+        let events = capture.events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].level, LogLevel::Warn);
+        assert_eq!(events[0].target, "multitool_hg::logger::capture::tests");
+        assert_eq!(events[0].message, "quota exceeded");
+        assert_eq!(events[0].field("user_id"), Some("5"));
+        assert_eq!(events[0].field("name"), Some("alice"));
+        assert_eq!(events[0].field("ok"), Some("true"));
+        assert_eq!(events[1].target, "custom_target");
+        assert!(capture.contains(LogLevel::Debug, "details"));
+        assert!(!capture.contains(LogLevel::Info, "details"));
+
+        capture.clear();
+        assert!(capture.events().is_empty());
+    }
+
+    /// Test that events are only captured while the guard is alive, and only on its thread.
+    #[test]
+    fn test_capture_scope() {
+        let capture = LogCapture::new();
+        {
+            let _guard = capture.start();
+            tracing::info!("inside");
+            std::thread::spawn(|| tracing::info!("other thread")).join().unwrap();
+        }
+        tracing::info!("outside");
+
+        let messages: Vec<String> = capture.events().into_iter().map(|event| event.message).collect();
+        assert_eq!(messages, vec!["inside"]);
+    }
+}
@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+
+use crate::logger::builder::LoggerBuilder;
+use crate::logger::otlp::OtlpConfig;
+use crate::logger::rate_limit::RateLimitConfig;
+use crate::logger::redact::RedactionConfig;
+use crate::logger::sink::{Sink, SinkDestination};
+use crate::logger::span_timing::SpanTimingConfig;
+use crate::logger::time::TimestampFormat;
+use crate::logger::tracer_logger::{LogDirectives, LogFormat, LogLevel};
+
+/// `LoggerConfig` represents the logging settings of a service.
+///
+/// It can be loaded from the same configuration file as `DatabaseConfig` and
+/// `RedisConfig`, and turned into a logger with
+/// [`new_tracer_logger_from_config`](crate::logger::tracer_logger::new_tracer_logger_from_config)
+/// or, for further customization, into a [`LoggerBuilder`] with `LoggerBuilder::from`.
+///
+/// Every field is optional; missing fields take the defaults of [`LoggerBuilder`].
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// level: info
+/// directives: sqlx=warn,my_service=debug
+/// format: compact
+/// ansi: true
+/// timestamps: true
+/// timestamp_format: local
+/// thread_ids: false
+/// thread_names: false
+/// targets: true
+/// source_location: false
+/// redaction:
+///   fields: [password, token, authorization]
+/// rate_limit:
+///   max_events: 100
+///   debug_sample_ratio: 0.1
+/// span_timing:
+///   thresholds:
+///     db_query: 200ms
+/// panic_hook: true
+/// sinks:
+///   - destination: stderr
+///   - destination:
+///       file:
+///         path: /var/log/my-service/service.log
+///         rotation: daily
+///     format: json
+///     level: debug
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LoggerConfig {
+    /// The default maximum level of events that are logged (default is info).
+    pub level: LogLevel,
+    /// `RUST_LOG`-style filter directives layered on top of the level.
+    pub directives: Option<LogDirectives>,
+    /// The default output format of log events (default is compact).
+    pub format: LogFormat,
+    /// The outputs of the logger; if empty, events are written to stdout.
+    pub sinks: Vec<Sink>,
+    /// Whether ANSI colors are used on stdout and stderr (default is true).
+    pub ansi: bool,
+    /// Whether events include a timestamp (default is true).
+    pub timestamps: bool,
+    /// How timestamps are rendered (default is RFC 3339 in UTC).
+    pub timestamp_format: TimestampFormat,
+    /// Whether events include the ID of the emitting thread (default is true).
+    pub thread_ids: bool,
+    /// Whether events include the name of the emitting thread (default is true).
+    pub thread_names: bool,
+    /// Whether events include their target (default is true).
+    pub targets: bool,
+    /// Whether events include the source file and line that emitted them (default is false).
+    pub source_location: bool,
+    /// Masks sensitive fields and values before any sink writes them.
+    pub redaction: Option<RedactionConfig>,
+    /// Limits the number of events per callsite and samples debug and trace events.
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Warns about spans that take longer than the configured thresholds.
+    pub span_timing: Option<SpanTimingConfig>,
+    /// Whether panics are logged as `error` events and the sinks flushed (default is false).
+    pub panic_hook: bool,
+    /// Exports spans to an OpenTelemetry collector; requires the `logger-otlp` feature.
+    pub otlp: Option<OtlpConfig>,
+}
+
+impl Default for LoggerConfig {
+    fn default() -> Self {
+        LoggerConfig {
+            level: LogLevel::default(),
+            directives: None,
+            format: LogFormat::default(),
+            sinks: Vec::new(),
+            ansi: true,
+            timestamps: true,
+            timestamp_format: TimestampFormat::default(),
+            thread_ids: true,
+            thread_names: true,
+            targets: true,
+            source_location: false,
+            redaction: None,
+            rate_limit: None,
+            span_timing: None,
+            panic_hook: false,
+            otlp: None,
+        }
+    }
+}
+
+impl LoggerConfig {
+    /// Checks the validity of the configuration.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the configuration is valid, or an `anyhow::Error` describing the problem.
+    pub fn check(&self) -> anyhow::Result<()> {
+        self.timestamp_format.check()?;
+        for sink in &self.sinks {
+            match &sink.destination {
+                SinkDestination::File(file) => file.check()?,
+                SinkDestination::Syslog(syslog) => syslog.check()?,
+                _ => {}
+            }
+        }
+        if let Some(redaction) = &self.redaction {
+            redaction.check()?;
+        }
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.check()?;
+        }
+        if let Some(span_timing) = &self.span_timing {
+            span_timing.check()?;
+        }
+        if let Some(otlp) = &self.otlp {
+            otlp.check()?;
+        }
+        Ok(())
+    }
+}
+
+impl From<LoggerConfig> for LoggerBuilder {
+    fn from(config: LoggerConfig) -> Self {
+        let mut builder = LoggerBuilder::new()
+            .level(config.level)
+            .format(config.format)
+            .ansi(config.ansi)
+            .timestamps(config.timestamps)
+            .timestamp_format(config.timestamp_format)
+            .thread_ids(config.thread_ids)
+            .thread_names(config.thread_names)
+            .targets(config.targets)
+            .source_location(config.source_location)
+            .panic_hook(config.panic_hook);
+        if let Some(directives) = config.directives {
+            builder = builder.directives(directives);
+        }
+        if let Some(redaction) = config.redaction {
+            builder = builder.redact(redaction);
+        }
+        if let Some(rate_limit) = config.rate_limit {
+            builder = builder.rate_limit(rate_limit);
+        }
+        if let Some(span_timing) = config.span_timing {
+            builder = builder.span_timing(span_timing);
+        }
+        if let Some(otlp) = config.otlp {
+            builder = builder.otlp(otlp);
+        }
+        config.sinks.into_iter().fold(builder, LoggerBuilder::sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logger::builder::tests::TestWriter;
+    use crate::logger::file::FileLogConfig;
+    use super::*;
+
+    /// Test that an empty configuration takes the defaults.
+    #[test]
+    fn test_deserialize_defaults() {
+        let config: LoggerConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, LoggerConfig::default());
+        assert!(config.check().is_ok());
+    }
+
+    /// Test deserializing a configuration with several sinks.
+    #[test]
+    fn test_deserialize_sinks() {
+        let config: LoggerConfig = serde_json::from_str(
+            r#"{
+                "level": "warn",
+                "directives": "sqlx=error",
+                "format": "pretty",
+                "thread_ids": false,
+                "sinks": [
+                    {"destination": "stderr"},
+                    {"destination": {"file": {"path": "/tmp/app.log"}}, "format": "json", "level": "debug"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.level, LogLevel::Warn);
+        assert_eq!(config.directives, Some("sqlx=error".parse().unwrap()));
+        assert_eq!(config.format, LogFormat::Pretty);
+        assert!(!config.thread_ids);
+        assert!(config.thread_names);
+        assert_eq!(
+            config.sinks,
+            vec![
+                Sink::stderr(),
+                Sink::file(FileLogConfig::new("/tmp/app.log")).format(LogFormat::Json).level(LogLevel::Debug),
+            ]
+        );
+    }
+
+    /// Test that durations accept the same formats as the other configurations.
+    #[test]
+    fn test_deserialize_durations() {
+        let config: LoggerConfig = serde_json::from_str(
+            r#"{
+                "rate_limit": {"interval": "500ms"},
+                "span_timing": {"default_threshold": 1.5, "thresholds": {"db_query": "200ms", "redis": {"secs": 1, "nanos": 0}}}
+            }"#,
+        )
+        .unwrap();
+
+        let rate_limit = config.rate_limit.unwrap();
+        assert_eq!(rate_limit.interval, std::time::Duration::from_millis(500));
+        let span_timing = config.span_timing.unwrap();
+        assert_eq!(span_timing.default_threshold, Some(std::time::Duration::from_millis(1500)));
+        assert_eq!(span_timing.thresholds["db_query"], std::time::Duration::from_millis(200));
+        assert_eq!(span_timing.thresholds["redis"], std::time::Duration::from_secs(1));
+    }
+
+    /// Test that invalid values are rejected with a descriptive error.
+    #[test]
+    fn test_deserialize_invalid() {
+        let err = serde_json::from_str::<LoggerConfig>(r#"{"directives": "my_crate=loud"}"#).unwrap_err();
+        assert!(err.to_string().contains("Invalid log directive"), "{}", err);
+        assert!(serde_json::from_str::<LoggerConfig>(r#"{"level": "verbose"}"#).is_err());
+    }
+
+    /// Test that the configured options are applied to the formatted output.
+    #[test]
+    fn test_options_applied() {
+        let config = LoggerConfig {
+            timestamps: false,
+            thread_ids: false,
+            thread_names: false,
+            targets: false,
+            ..LoggerConfig::default()
+        };
+        let writer = TestWriter::default();
+        let (subscriber, _handle) = LoggerBuilder::from(config).build_with_writer(writer.clone(), false);
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("bare event");
+        });
+
+        assert_eq!(writer.output(), " INFO bare event\n");
+    }
+
+    /// Test that the timestamp format and source locations are applied, and invalid formats rejected.
+    #[test]
+    fn test_timestamp_and_source_location() {
+        let config: LoggerConfig = serde_json::from_str(
+            r#"{"timestamp_format": "uptime", "source_location": true, "thread_ids": false, "thread_names": false}"#,
+        )
+        .unwrap();
+        assert!(config.check().is_ok());
+        let writer = TestWriter::default();
+        let (subscriber, _handle) = LoggerBuilder::from(config).build_with_writer(writer.clone(), false);
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("located event");
+        });
+
+        let output = writer.output();
+        let (uptime, rest) = output.split_once(' ').unwrap();
+        assert!(uptime.starts_with("0.") && uptime.ends_with('s'), "{}", output);
+        assert!(rest.contains("src/logger/config.rs:"), "{}", output);
+
+        let config: LoggerConfig = serde_json::from_str(r#"{"timestamp_format": {"custom": "%Y-%Q"}}"#).unwrap();
+        let err = config.check().unwrap_err();
+        assert!(err.to_string().contains("Invalid timestamp format"), "{}", err);
+    }
+}
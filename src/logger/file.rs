@@ -0,0 +1,231 @@
+use std::path::PathBuf;
+
+use file_rotate::compression::Compression;
+use file_rotate::suffix::{AppendCount, AppendTimestamp, DateFrom, FileLimit};
+use file_rotate::{ContentLimit, FileRotate, TimeFrequency};
+use serde::{Deserialize, Serialize};
+
+use crate::logger::writer::NonBlocking;
+
+/// `Rotation` defines when the current log file is closed and a new one is started.
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// rotation: daily
+/// ```
+///
+/// ```yaml
+/// rotation:
+///   size: 104857600 # 100 MiB
+/// ```
+#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rotation {
+    /// Never rotate; the file grows indefinitely.
+    Never,
+    /// Start a new file every hour. Rotated files are suffixed with the hour they cover.
+    Hourly,
+    /// Start a new file every day. Rotated files are suffixed with the day they cover.
+    #[default]
+    Daily,
+    /// Start a new file once the current one exceeds the given number of bytes.
+    /// Rotated files are suffixed with `.1`, `.2`, ... from newest to oldest.
+    Size(u64),
+}
+
+/// `FileLogConfig` describes a log file sink with rotation and retention.
+///
+/// Events are handed to a background thread that performs the actual disk I/O, so
+/// logging never blocks on a slow disk. If the disk falls far behind, events are
+/// dropped and a warning reports how many.
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// path: /var/log/my-service/service.log
+/// rotation: daily
+/// max_files: 14
+/// compress: true
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FileLogConfig {
+    /// The path of the active log file. Rotated files are created next to it.
+    pub path: PathBuf,
+    /// When to rotate the log file (default is daily).
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// The maximum number of rotated files to keep; older files are deleted (default is 7).
+    #[serde(default = "FileLogConfig::default_max_files")]
+    pub max_files: usize,
+    /// Whether rotated files are compressed with gzip (default is false).
+    #[serde(default)]
+    pub compress: bool,
+}
+
+impl FileLogConfig {
+    /// Creates a configuration for `path` with daily rotation, 7 retained files and no compression.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileLogConfig {
+            path: path.into(),
+            rotation: Rotation::default(),
+            max_files: Self::default_max_files(),
+            compress: false,
+        }
+    }
+
+    fn default_max_files() -> usize {
+        7
+    }
+
+    /// Checks the validity of the configuration.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the configuration is valid, or an `anyhow::Error` describing the problem.
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.path.file_name().is_none() {
+            return Err(anyhow::Error::msg(format!("Log file path `{}` must name a file.", self.path.display())));
+        }
+        if self.rotation == Rotation::Size(0) {
+            return Err(anyhow::Error::msg("Log file rotation size must be greater than zero."));
+        }
+        if self.max_files == 0 {
+            return Err(anyhow::Error::msg("Field `max_files` must be greater than zero."));
+        }
+        Ok(())
+    }
+
+    /// Opens the log file, creating its directory if needed, and starts the background writer.
+    ///
+    /// Fails if the file cannot be opened for appending.
+    pub(crate) fn open(&self) -> anyhow::Result<NonBlocking> {
+        self.check()?;
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|err| anyhow::Error::msg(format!("Failed to create log directory `{}`: {}", dir.display(), err)))?;
+        }
+        // `FileRotate` ignores errors from opening the file and then drops every write,
+        // so the file is opened once up front to report them.
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| anyhow::Error::msg(format!("Failed to open log file `{}`: {}", self.path.display(), err)))?;
+
+        let compression = if self.compress {
+            Compression::OnRotate(0)
+        } else {
+            Compression::None
+        };
+        let file_limit = FileLimit::MaxFiles(self.max_files);
+
+        let writer = match self.rotation {
+            Rotation::Never => NonBlocking::new(self.rotate(AppendCount::new(self.max_files), ContentLimit::None, compression)),
+            Rotation::Hourly => NonBlocking::new(self.rotate(
+                AppendTimestamp::with_format("%Y%m%dT%H", file_limit, DateFrom::DateHourAgo),
+                ContentLimit::Time(TimeFrequency::Hourly),
+                compression,
+            )),
+            Rotation::Daily => NonBlocking::new(self.rotate(
+                AppendTimestamp::with_format("%Y%m%d", file_limit, DateFrom::DateYesterday),
+                ContentLimit::Time(TimeFrequency::Daily),
+                compression,
+            )),
+            Rotation::Size(bytes) => NonBlocking::new(self.rotate(
+                AppendCount::new(self.max_files),
+                ContentLimit::BytesSurpassed(usize::try_from(bytes).unwrap_or(usize::MAX)),
+                compression,
+            )),
+        };
+        Ok(writer?)
+    }
+
+    fn rotate<S>(&self, suffix: S, limit: ContentLimit, compression: Compression) -> FileRotate<S>
+    where
+        S: file_rotate::suffix::SuffixScheme,
+    {
+        FileRotate::new(
+            &self.path,
+            suffix,
+            limit,
+            compression,
+            #[cfg(unix)]
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("multitool-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Test that the configuration can be deserialized with defaults.
+    #[test]
+    fn test_deserialize_defaults() {
+        let config: FileLogConfig = serde_json::from_str(r#"{"path": "/var/log/app.log"}"#).unwrap();
+        assert_eq!(config, FileLogConfig::new("/var/log/app.log"));
+
+        let config: FileLogConfig =
+            serde_json::from_str(r#"{"path": "app.log", "rotation": {"size": 1024}, "max_files": 3, "compress": true}"#)
+                .unwrap();
+        assert_eq!(config.rotation, Rotation::Size(1024));
+        assert_eq!(config.max_files, 3);
+        assert!(config.compress);
+    }
+
+    /// Test that invalid configurations are rejected.
+    #[test]
+    fn test_check() {
+        assert!(FileLogConfig::new("app.log").check().is_ok());
+        assert!(FileLogConfig { max_files: 0, ..FileLogConfig::new("app.log") }.check().is_err());
+        assert!(FileLogConfig { rotation: Rotation::Size(0), ..FileLogConfig::new("app.log") }.check().is_err());
+    }
+
+    /// Test size-based rotation with retention and compression.
+    #[test]
+    fn test_size_rotation() {
+        let dir = temp_dir("size-rotation");
+        let config = FileLogConfig {
+            rotation: Rotation::Size(100),
+            max_files: 2,
+            compress: true,
+            ..FileLogConfig::new(dir.join("logs").join("app.log"))
+        };
+
+        let mut writer = config.open().unwrap();
+        for i in 0..20 {
+            writer.write_all(format!("{:0>40}\n", i).as_bytes()).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let mut files: Vec<String> = std::fs::read_dir(dir.join("logs"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["app.log", "app.log.1.gz", "app.log.2.gz"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Test that a log file which cannot be opened fails `open` instead of dropping events.
+    #[test]
+    fn test_open_unwritable_path() {
+        let dir = temp_dir("unwritable");
+        // A directory cannot be opened for appending, even by root.
+        std::fs::create_dir_all(dir.join("app.log")).unwrap();
+
+        let err = FileLogConfig::new(dir.join("app.log")).open().err().unwrap();
+        assert!(err.to_string().contains("Failed to open log file"), "{}", err);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+use std::io::Write;
+#[cfg(feature = "logger-otlp")]
+use std::sync::Arc;
+
+use crate::logger::handle::LoggerHandle;
+use crate::logger::writer::NonBlocking;
+
+/// `LoggerGuard` keeps the installed logger's writers alive and flushes them on drop.
+///
+/// It is returned by [`LoggerBuilder::init`](crate::logger::builder::LoggerBuilder::init)
+/// and [`LoggerBuilder::try_init`](crate::logger::builder::LoggerBuilder::try_init).
+/// Hold it for as long as the application runs, typically by binding it in `main`:
+///
+/// ```rust
+/// use multitool_hg::logger::builder::LoggerBuilder;
+///
+/// fn main() {
+///     let _guard = LoggerBuilder::new().init();
+///     // Your application logic here...
+/// }
+/// ```
+///
+/// Dropping the guard does not uninstall the logger; it only waits until every event
+/// logged so far has been written, including events queued for background file writers
+/// and spans waiting to be exported over OTLP. Once the guard returned by the call that
+/// installed the logger and all of its clones have been dropped, the OTLP exporter is
+/// shut down as well; guards returned by later, no-op calls do not own the exporter.
+#[must_use = "dropping the guard flushes the logger's writers immediately"]
+#[derive(Clone, Debug)]
+pub struct LoggerGuard {
+    handle: LoggerHandle,
+    writers: Vec<NonBlocking>,
+    #[cfg(feature = "logger-otlp")]
+    tracer_provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+    /// Shuts the tracer provider down once the last owning clone has been dropped.
+    #[cfg(feature = "logger-otlp")]
+    shutdown: Option<Arc<TracerProviderShutdown>>,
+}
+
+impl LoggerGuard {
+    pub(crate) fn new(handle: LoggerHandle, writers: Vec<NonBlocking>) -> Self {
+        LoggerGuard {
+            handle,
+            writers,
+            #[cfg(feature = "logger-otlp")]
+            tracer_provider: None,
+            #[cfg(feature = "logger-otlp")]
+            shutdown: None,
+        }
+    }
+
+    /// Attaches the OpenTelemetry tracer provider, so that pending spans are exported on drop.
+    #[cfg(feature = "logger-otlp")]
+    pub(crate) fn with_tracer_provider(mut self, tracer_provider: opentelemetry_sdk::trace::SdkTracerProvider) -> Self {
+        self.shutdown = Some(Arc::new(TracerProviderShutdown(tracer_provider.clone())));
+        self.tracer_provider = Some(tracer_provider);
+        self
+    }
+
+    /// Returns a clone that flushes the logger but does not keep the OTLP exporter from
+    /// being shut down, for holders that live until the end of the process.
+    pub(crate) fn detached(&self) -> Self {
+        LoggerGuard {
+            handle: self.handle.clone(),
+            writers: self.writers.clone(),
+            #[cfg(feature = "logger-otlp")]
+            tracer_provider: self.tracer_provider.clone(),
+            #[cfg(feature = "logger-otlp")]
+            shutdown: None,
+        }
+    }
+
+    /// Returns the handle for changing the logger's filtering at runtime.
+    pub fn handle(&self) -> &LoggerHandle {
+        &self.handle
+    }
+
+    /// Waits until every event logged so far has been written or exported.
+    pub(crate) fn flush(&self) {
+        for writer in &self.writers {
+            writer.sync();
+        }
+        #[cfg(feature = "logger-otlp")]
+        if let Some(tracer_provider) = &self.tracer_provider {
+            let _ = tracer_provider.force_flush();
+        }
+        let _ = std::io::stdout().flush();
+        let _ = std::io::stderr().flush();
+    }
+}
+
+impl Drop for LoggerGuard {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Shuts the wrapped tracer provider down when dropped, after its spans have been flushed.
+#[cfg(feature = "logger-otlp")]
+#[derive(Debug)]
+struct TracerProviderShutdown(opentelemetry_sdk::trace::SdkTracerProvider);
+
+#[cfg(feature = "logger-otlp")]
+impl Drop for TracerProviderShutdown {
+    fn drop(&mut self) {
+        let _ = self.0.shutdown();
+    }
+}
+
+#[cfg(all(test, feature = "logger-otlp"))]
+mod tests {
+    use opentelemetry_sdk::error::OTelSdkError;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+
+    use crate::logger::tracer_logger::LogLevel;
+    use super::*;
+
+    /// Test that the tracer provider is shut down with the last owning clone of the guard.
+    #[test]
+    fn test_shutdown_with_last_owner() {
+        let provider = SdkTracerProvider::builder().build();
+        let guard = LoggerGuard::new(LoggerHandle::new(LogLevel::Info, None), Vec::new()).with_tracer_provider(provider.clone());
+        let detached = guard.detached();
+        let clone = guard.clone();
+
+        drop(guard);
+        drop(detached);
+        assert!(provider.force_flush().is_ok());
+
+        drop(clone);
+        assert!(matches!(provider.shutdown(), Err(OTelSdkError::AlreadyShutdown)));
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::{Arc, Mutex};
+
+use tracing_subscriber::filter::EnvFilter;
+use tracing_subscriber::{reload, Registry};
+
+use crate::logger::builder::sync_log_max_level;
+use crate::logger::tracer_logger::{env_filter, LogDirectives, LogLevel};
+
+/// The reloadable filter applied to the logger's output.
+pub(crate) type ReloadFilter = reload::Layer<EnvFilter, Registry>;
+
+/// `LoggerHandle` changes the filtering of an installed logger at runtime.
+///
+/// It is obtained from the [`LoggerGuard`](crate::logger::guard::LoggerGuard) returned by
+/// [`LoggerBuilder::init`](crate::logger::builder::LoggerBuilder::init) and is cheap to
+/// clone, so it can be handed to an admin endpoint or a signal handler to raise or lower
+/// verbosity without restarting the service.
+///
+/// The level and the directives are tracked separately: changing one keeps the other.
+///
+/// # Example
+/// ```rust
+/// use multitool_hg::logger::builder::LoggerBuilder;
+/// use multitool_hg::logger::tracer_logger::LogLevel;
+///
+/// fn main() -> anyhow::Result<()> {
+///     let guard = LoggerBuilder::new().level(LogLevel::Info).init();
+///     let handle = guard.handle().clone();
+///
+///     // Later, e.g. from an admin endpoint:
+///     handle.set_level(LogLevel::Debug)?;
+///     handle.set_directives("sqlx=warn")?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct LoggerHandle {
+    state: Arc<Mutex<FilterState>>,
+}
+
+#[derive(Debug)]
+struct FilterState {
+    level: LogLevel,
+    directives: Option<LogDirectives>,
+    sinks: Vec<SinkFilter>,
+}
+
+/// The reloadable filter of a single sink, along with the settings it overrides.
+#[derive(Debug)]
+struct SinkFilter {
+    level: Option<LogLevel>,
+    directives: Option<LogDirectives>,
+    handle: reload::Handle<EnvFilter, Registry>,
+}
+
+impl FilterState {
+    /// Rebuilds every sink filter from the current level and directives.
+    fn reload(&self) -> anyhow::Result<()> {
+        for sink in &self.sinks {
+            sink.handle.reload(self.filter_for(&sink.level, &sink.directives))?;
+        }
+        sync_log_max_level();
+        Ok(())
+    }
+
+    fn filter_for(&self, level: &Option<LogLevel>, directives: &Option<LogDirectives>) -> EnvFilter {
+        let level = level.as_ref().unwrap_or(&self.level);
+        let directives = directives.as_ref().or(self.directives.as_ref());
+        env_filter(level, directives)
+    }
+}
+
+impl LoggerHandle {
+    /// Creates a handle for a logger with the given default `level` and `directives`.
+    pub(crate) fn new(level: LogLevel, directives: Option<LogDirectives>) -> Self {
+        let state = FilterState {
+            level,
+            directives,
+            sinks: Vec::new(),
+        };
+        LoggerHandle {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Creates the reloadable filter for a sink.
+    ///
+    /// The sink's own `level` and `directives`, if set, take precedence over the
+    /// logger's defaults; whatever is not set follows the defaults, including when
+    /// they are changed through this handle.
+    pub(crate) fn sink_filter(&self, level: Option<LogLevel>, directives: Option<LogDirectives>) -> ReloadFilter {
+        let mut state = self.state();
+        let (filter, handle) = reload::Layer::new(state.filter_for(&level, &directives));
+        state.sinks.push(SinkFilter { level, directives, handle });
+        filter
+    }
+
+    /// Returns the current default log level.
+    pub fn level(&self) -> LogLevel {
+        self.state().level
+    }
+
+    /// Returns the current default filter directives, if any.
+    pub fn directives(&self) -> Option<LogDirectives> {
+        self.state().directives.clone()
+    }
+
+    /// Changes the default log level while keeping the current directives.
+    ///
+    /// Sinks configured with their own level are not affected.
+    ///
+    /// # Errors
+    /// Returns an error if the logger this handle belongs to has been dropped.
+    pub fn set_level(&self, level: LogLevel) -> anyhow::Result<()> {
+        let mut state = self.state();
+        state.level = level;
+        state.reload()
+    }
+
+    /// Replaces the default filter directives (e.g. `my_service=debug,sqlx=warn`) while
+    /// keeping the current level. An empty string removes all directives.
+    ///
+    /// Sinks configured with their own directives are not affected.
+    ///
+    /// # Errors
+    /// Returns an error if the directives are malformed or if the logger this handle
+    /// belongs to has been dropped.
+    pub fn set_directives(&self, directives: &str) -> anyhow::Result<()> {
+        let directives: LogDirectives = directives.parse().map_err(anyhow::Error::msg)?;
+        let mut state = self.state();
+        state.directives = Some(directives).filter(|directives| !directives.to_string().is_empty());
+        state.reload()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, FilterState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+/// `JournaldConfig` describes a sink that sends events to systemd-journald over its
+/// native protocol.
+///
+/// Writing to journald requires the `logger-journald` feature and a running journald,
+/// reached through the socket `/run/systemd/journal/socket`. Levels are mapped to
+/// priorities: `error` to err (3), `warn` to warning (4), `info` to notice (5),
+/// `debug` to info (6) and `trace` to debug (7). The event's target, source location
+/// and fields are sent as journal fields; field names are upper-cased and prefixed
+/// with `field_prefix`. Event and span fields are masked by the logger's redaction, as
+/// in every other sink.
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// syslog_identifier: my-service
+/// field_prefix: APP
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct JournaldConfig {
+    /// The `SYSLOG_IDENTIFIER` of the entries; defaults to the name of the executable.
+    #[serde(default)]
+    pub syslog_identifier: Option<String>,
+    /// The prefix of the journal fields holding event fields (default is `F`).
+    #[serde(default)]
+    pub field_prefix: Option<String>,
+}
+
+impl JournaldConfig {
+    /// Connects to journald and builds the layer sending events to it.
+    #[cfg(feature = "logger-journald")]
+    pub(crate) fn layer(&self) -> anyhow::Result<tracing_journald::Layer> {
+        let mut layer = tracing_journald::layer()
+            .map_err(|err| anyhow::Error::msg(format!("Failed to connect to journald: {}", err)))?;
+        if let Some(identifier) = &self.syslog_identifier {
+            layer = layer.with_syslog_identifier(identifier.clone());
+        }
+        if let Some(prefix) = &self.field_prefix {
+            layer = layer.with_field_prefix(Some(prefix.clone()));
+        }
+        Ok(layer)
+    }
+}
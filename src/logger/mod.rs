@@ -1 +1,18 @@
 pub mod tracer_logger;
+pub mod builder;
+pub mod handle;
+pub mod guard;
+pub mod file;
+pub mod sink;
+pub mod otlp;
+pub mod config;
+pub mod args;
+pub mod redact;
+pub mod rate_limit;
+pub mod capture;
+pub mod journald;
+pub mod syslog;
+pub mod time;
+pub mod span_timing;
+mod panic;
+mod writer;
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// `OtlpProtocol` defines the transport used to export spans to the collector.
+#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OtlpProtocol {
+    /// OTLP over gRPC (usually port 4317). The logger must be initialized within a Tokio
+    /// runtime, which must keep running while spans are exported; otherwise initialization fails.
+    #[default]
+    Grpc,
+    /// OTLP over HTTP with protobuf payloads (usually port 4318).
+    HttpProtobuf,
+}
+
+/// `OtlpConfig` configures the export of spans to an OpenTelemetry collector over OTLP.
+///
+/// Exporting requires the `logger-otlp` feature. Spans are filtered by the logger's
+/// default level and directives and exported in batches from a background thread.
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// endpoint: http://otel-collector:4318
+/// protocol: http-protobuf
+/// service_name: my-service
+/// resource_attributes:
+///   deployment.environment: production
+/// sampling_ratio: 0.25
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OtlpConfig {
+    /// The collector endpoint, e.g. `http://localhost:4317` for gRPC or `http://localhost:4318`
+    /// for HTTP. For HTTP, `/v1/traces` is appended unless the endpoint already ends with it.
+    pub endpoint: String,
+    /// The transport protocol (default is gRPC).
+    #[serde(default)]
+    pub protocol: OtlpProtocol,
+    /// The `service.name` resource attribute reported with every span.
+    pub service_name: String,
+    /// Additional resource attributes, such as `deployment.environment`.
+    #[serde(default)]
+    pub resource_attributes: BTreeMap<String, String>,
+    /// The fraction of new traces that are sampled, between 0.0 and 1.0 (default is 1.0).
+    /// Child spans follow the sampling decision of their parent.
+    #[serde(default = "OtlpConfig::default_sampling_ratio")]
+    pub sampling_ratio: f64,
+}
+
+impl OtlpConfig {
+    /// Creates a configuration exporting every trace over gRPC to `endpoint`.
+    pub fn new(endpoint: impl Into<String>, service_name: impl Into<String>) -> Self {
+        OtlpConfig {
+            endpoint: endpoint.into(),
+            protocol: OtlpProtocol::default(),
+            service_name: service_name.into(),
+            resource_attributes: BTreeMap::new(),
+            sampling_ratio: Self::default_sampling_ratio(),
+        }
+    }
+
+    fn default_sampling_ratio() -> f64 {
+        1.0
+    }
+
+    /// Checks the validity of the configuration.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the configuration is valid, or an `anyhow::Error` describing the problem.
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.endpoint.is_empty() {
+            return Err(anyhow::Error::msg("Field `endpoint` must be set for OTLP export."));
+        }
+        if self.service_name.is_empty() {
+            return Err(anyhow::Error::msg("Field `service_name` must be set for OTLP export."));
+        }
+        if !(0.0..=1.0).contains(&self.sampling_ratio) {
+            return Err(anyhow::Error::msg(format!(
+                "Field `sampling_ratio` must be between 0.0 and 1.0, got {}.",
+                self.sampling_ratio
+            )));
+        }
+        Ok(())
+    }
+
+    /// Builds the tracer provider that exports spans according to this configuration.
+    #[cfg(feature = "logger-otlp")]
+    pub(crate) fn tracer_provider(&self) -> anyhow::Result<opentelemetry_sdk::trace::SdkTracerProvider> {
+        use opentelemetry::KeyValue;
+        use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+        use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+        use opentelemetry_sdk::Resource;
+
+        self.check()?;
+
+        let exporter = match self.protocol {
+            OtlpProtocol::Grpc => {
+                // The gRPC client panics when it is created outside a Tokio runtime.
+                if tokio::runtime::Handle::try_current().is_err() {
+                    return Err(anyhow::Error::msg(
+                        "Exporting spans over OTLP/gRPC requires the logger to be initialized within a Tokio runtime.",
+                    ));
+                }
+                SpanExporter::builder()
+                    .with_tonic()
+                    .with_endpoint(&self.endpoint)
+                    .build()?
+            }
+            OtlpProtocol::HttpProtobuf => {
+                let endpoint = if self.endpoint.ends_with("/v1/traces") {
+                    self.endpoint.clone()
+                } else {
+                    format!("{}/v1/traces", self.endpoint.trim_end_matches('/'))
+                };
+                SpanExporter::builder()
+                    .with_http()
+                    .with_protocol(Protocol::HttpBinary)
+                    .with_endpoint(endpoint)
+                    .build()?
+            }
+        };
+
+        let resource = Resource::builder()
+            .with_service_name(self.service_name.clone())
+            .with_attributes(
+                self.resource_attributes
+                    .iter()
+                    .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+            )
+            .build();
+        let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(self.sampling_ratio)));
+
+        Ok(SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_sampler(sampler)
+            .with_resource(resource)
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the configuration can be deserialized with defaults.
+    #[test]
+    fn test_deserialize_defaults() {
+        let config: OtlpConfig =
+            serde_json::from_str(r#"{"endpoint": "http://localhost:4317", "service_name": "my-service"}"#).unwrap();
+        assert_eq!(config, OtlpConfig::new("http://localhost:4317", "my-service"));
+    }
+
+    /// Test that invalid configurations are rejected.
+    #[test]
+    fn test_check() {
+        let config = OtlpConfig::new("http://localhost:4317", "my-service");
+        assert!(config.check().is_ok());
+        assert!(OtlpConfig { sampling_ratio: 1.5, ..config.clone() }.check().is_err());
+        assert!(OtlpConfig { service_name: String::new(), ..config }.check().is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::panic::Location;
+
+use crate::logger::guard::LoggerGuard;
+
+/// Wraps the panic hook with one that logs panics as `error` events and flushes
+/// the logger's sinks, so that the panic is recorded even if the process aborts.
+/// The previous hook is called afterwards, so test harnesses and other hooks still
+/// see the panic.
+///
+/// The event has the target `panic` and the fields `panic.message`, `panic.location`,
+/// `panic.thread` and `panic.backtrace`. The backtrace is only captured if enabled
+/// through `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`.
+pub(crate) fn install(guard: LoggerGuard) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log_panic(info.payload(), info.location());
+        guard.flush();
+        previous(info);
+    }));
+}
+
+fn log_panic(payload: &(dyn Any + Send), location: Option<&Location<'_>>) {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let location = location.map_or_else(|| "unknown".to_string(), ToString::to_string);
+    let thread = std::thread::current();
+    let thread = thread.name().unwrap_or("<unnamed>");
+    let backtrace = Backtrace::capture();
+
+    tracing::error!(
+        target: "panic",
+        {
+            panic.message = message,
+            panic.location = location,
+            panic.thread = thread,
+            panic.backtrace = %backtrace,
+        },
+        "thread '{}' panicked at {}: {}",
+        thread,
+        location,
+        message,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::logger::builder::LoggerBuilder;
+    use crate::logger::capture::LogCapture;
+    use crate::logger::tracer_logger::LogLevel;
+    use super::*;
+
+    /// Test that panics are logged as structured error events.
+    #[test]
+    fn test_log_panic() {
+        let capture = LogCapture::new();
+        let location = Location::caller();
+        capture.capture(|| {
+            log_panic(&"boom", Some(location));
+            log_panic(&String::from("formatted boom"), None);
+        });
+
+        let events = capture.events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].level, LogLevel::Error);
+        assert_eq!(events[0].target, "panic");
+        assert_eq!(events[0].field("panic.message"), Some("boom"));
+        assert_eq!(events[0].field("panic.location"), Some(location.to_string().as_str()));
+        assert_eq!(events[0].field("panic.thread"), Some("logger::panic::tests::test_log_panic"));
+        assert!(events[0].field("panic.backtrace").is_some());
+        assert!(events[0].message.contains("panicked at"), "{}", events[0].message);
+        assert_eq!(events[1].field("panic.message"), Some("formatted boom"));
+        assert_eq!(events[1].field("panic.location"), Some("unknown"));
+    }
+
+    /// Test that the installed hook logs the panic and then calls the previous hook.
+    ///
+    /// The panic hook is global to the process, so the test runs in a child process
+    /// where it cannot catch the panics of tests running in parallel.
+    #[test]
+    fn test_install_calls_previous_hook() {
+        const CHILD: &str = "MULTITOOL_PANIC_HOOK_CHILD";
+        if std::env::var_os(CHILD).is_none() {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["logger::panic::tests::test_install_calls_previous_hook", "--exact", "--test-threads=1"])
+                .env(CHILD, "1")
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(output.status.success(), "{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+            assert!(stdout.contains("1 passed"), "{}", stdout);
+            return;
+        }
+
+        static PREVIOUS_CALLED: AtomicBool = AtomicBool::new(false);
+
+        std::panic::set_hook(Box::new(|_| PREVIOUS_CALLED.store(true, Ordering::SeqCst)));
+        let (_subscriber, guard) = LoggerBuilder::new().build().unwrap();
+        install(guard);
+
+        let capture = LogCapture::new();
+        let result = capture.capture(|| std::panic::catch_unwind(|| panic!("hooked boom")));
+        drop(std::panic::take_hook());
+
+        assert!(result.is_err());
+        assert!(PREVIOUS_CALLED.load(Ordering::SeqCst));
+        assert_eq!(capture.events()[0].field("panic.message"), Some("hooked boom"));
+    }
+}
@@ -0,0 +1,343 @@
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::callsite::Identifier;
+use tracing::dispatcher::WeakDispatch;
+use tracing::{Dispatch, Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// `RateLimitConfig` limits how many log events each callsite may emit, and samples
+/// verbose events.
+///
+/// Every `log::error!`/`tracing::error!` invocation in the source is a separate callsite.
+/// A callsite may emit at most `max_events` events per `interval`; further events in
+/// the same interval are dropped. Once per interval, a warning such as
+/// `suppressed 1234 events from my_service::db (src/db.rs:42)` is logged for every
+/// callsite that dropped events.
+///
+/// Independently, debug and trace events are kept only with the probability given by
+/// `debug_sample_ratio` and `trace_sample_ratio`.
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// max_events: 100
+/// interval: 1s
+/// debug_sample_ratio: 0.1
+/// trace_sample_ratio: 0.01
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// The maximum number of events per callsite and interval (default is 100).
+    pub max_events: u32,
+    /// The length of the rate limiting interval, e.g. `1s` or `500ms` (default is one second).
+    #[serde(with = "crate::duration")]
+    pub interval: Duration,
+    /// The fraction of debug events that are kept, between 0.0 and 1.0 (default is 1.0).
+    pub debug_sample_ratio: f64,
+    /// The fraction of trace events that are kept, between 0.0 and 1.0 (default is 1.0).
+    pub trace_sample_ratio: f64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            max_events: 100,
+            interval: Duration::from_secs(1),
+            debug_sample_ratio: 1.0,
+            trace_sample_ratio: 1.0,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Checks the validity of the configuration.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the configuration is valid, or an `anyhow::Error` describing the problem.
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.max_events == 0 {
+            return Err(anyhow::Error::msg("Field `max_events` must be greater than zero."));
+        }
+        if self.interval.is_zero() {
+            return Err(anyhow::Error::msg("Field `interval` must be greater than zero."));
+        }
+        for (name, ratio) in [("debug_sample_ratio", self.debug_sample_ratio), ("trace_sample_ratio", self.trace_sample_ratio)] {
+            if !(0.0..=1.0).contains(&ratio) {
+                return Err(anyhow::Error::msg(format!("Field `{}` must be between 0.0 and 1.0, got {}.", name, ratio)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The events seen from one callsite in the current interval.
+struct Window {
+    metadata: &'static Metadata<'static>,
+    start: Instant,
+    events: u32,
+    suppressed: u64,
+}
+
+type Windows = Arc<Mutex<HashMap<Identifier, Window>>>;
+
+/// Returns the current time; replaced in tests to control the intervals.
+type Clock = Arc<dyn Fn() -> Instant + Send + Sync>;
+
+/// A layer that drops events exceeding the configured rate or losing the sampling draw.
+///
+/// It must be placed before the layers it protects, as it only decides whether the
+/// remaining layers see an event. Suppressed events are reported from a background
+/// thread, since events cannot be emitted while another event is being dispatched.
+/// Clones share their state.
+#[derive(Clone)]
+pub(crate) struct RateLimitLayer {
+    config: RateLimitConfig,
+    windows: Windows,
+    reporter: Arc<Once>,
+    clock: Clock,
+    /// The state of the sampling generator if seeded; otherwise a per-thread random state is used.
+    rng: Option<Arc<Mutex<u64>>>,
+}
+
+impl RateLimitLayer {
+    pub(crate) fn new(config: RateLimitConfig) -> anyhow::Result<Self> {
+        config.check()?;
+        Ok(RateLimitLayer {
+            config,
+            windows: Arc::new(Mutex::new(HashMap::new())),
+            reporter: Arc::new(Once::new()),
+            clock: Arc::new(Instant::now),
+            rng: None,
+        })
+    }
+
+    /// Uses `clock` instead of the system clock to decide when intervals end.
+    #[cfg(test)]
+    fn with_clock(mut self, clock: impl Fn() -> Instant + Send + Sync + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Samples events with a generator seeded with `seed`, so that the draws are reproducible.
+    #[cfg(test)]
+    fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Some(Arc::new(Mutex::new(seed | 1)));
+        self
+    }
+
+    fn sampled(&self, level: &Level) -> bool {
+        let ratio = match *level {
+            Level::DEBUG => self.config.debug_sample_ratio,
+            Level::TRACE => self.config.trace_sample_ratio,
+            _ => return true,
+        };
+        if ratio >= 1.0 {
+            return true;
+        }
+        let draw = match &self.rng {
+            Some(state) => xorshift(&mut state.lock().unwrap_or_else(|err| err.into_inner())),
+            None => random(),
+        };
+        draw < ratio
+    }
+
+    /// Logs a warning for every callsite that dropped events since the last report,
+    /// through the current dispatcher, without waiting for the background reporter.
+    #[cfg(test)]
+    fn report(&self) {
+        report_suppressed(&self.windows);
+    }
+
+    /// Counts the event and returns whether it is within the limit.
+    fn admit(&self, metadata: &'static Metadata<'static>) -> bool {
+        let now = (self.clock)();
+        let mut windows = lock(&self.windows);
+        let window = windows.entry(metadata.callsite()).or_insert(Window {
+            metadata,
+            start: now,
+            events: 0,
+            suppressed: 0,
+        });
+
+        if now.duration_since(window.start) >= self.config.interval {
+            window.start = now;
+            window.events = 0;
+        }
+        window.events = window.events.saturating_add(1);
+        let admitted = window.events <= self.config.max_events;
+        if !admitted {
+            window.suppressed += 1;
+        }
+        admitted
+    }
+}
+
+impl<S: Subscriber> Layer<S> for RateLimitLayer {
+    fn on_register_dispatch(&self, dispatch: &Dispatch) {
+        self.reporter.call_once(|| {
+            let dispatch = dispatch.downgrade();
+            let windows = self.windows.clone();
+            let interval = self.config.interval;
+            // The reporter exits once the subscriber has been dropped.
+            let _ = thread::Builder::new()
+                .name("multitool-log-rate-limit".to_string())
+                .spawn(move || report(dispatch, windows, interval));
+        });
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        let metadata = event.metadata();
+        self.sampled(metadata.level()) && self.admit(metadata)
+    }
+}
+
+/// Logs a warning for every callsite that dropped events, once per `interval`.
+fn report(dispatch: WeakDispatch, windows: Windows, interval: Duration) {
+    loop {
+        thread::sleep(interval);
+        let Some(dispatch) = dispatch.upgrade() else {
+            return;
+        };
+        tracing::dispatcher::with_default(&dispatch, || report_suppressed(&windows));
+    }
+}
+
+/// Logs a warning for every callsite that dropped events, and resets their counts.
+fn report_suppressed(windows: &Windows) {
+    let reports: Vec<_> = lock(windows)
+        .values_mut()
+        .filter(|window| window.suppressed > 0)
+        .map(|window| (window.metadata, std::mem::take(&mut window.suppressed)))
+        .collect();
+
+    for (metadata, suppressed) in reports {
+        tracing::warn!(
+            target: "multitool_hg::logger::rate_limit",
+            suppressed,
+            callsite.target = metadata.target(),
+            "suppressed {} events from {} ({}:{})",
+            suppressed,
+            metadata.target(),
+            metadata.file().unwrap_or("unknown"),
+            metadata.line().unwrap_or(0),
+        );
+    }
+}
+
+fn lock(windows: &Windows) -> MutexGuard<'_, HashMap<Identifier, Window>> {
+    windows.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Returns a pseudo-random number in `[0, 1)`, good enough for sampling.
+fn random() -> f64 {
+    thread_local! {
+        static STATE: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish() | 1);
+    }
+    STATE.with(|state| {
+        let mut x = state.get();
+        let draw = xorshift(&mut x);
+        state.set(x);
+        draw
+    })
+}
+
+/// Advances the xorshift64* generator `state` and returns a number in `[0, 1)`.
+fn xorshift(state: &mut u64) -> f64 {
+    let mut x = *state;
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    *state = x;
+    (x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+    use crate::logger::builder::fmt_layer;
+    use crate::logger::builder::tests::{plain, TestWriter};
+    use crate::logger::tracer_logger::LogFormat;
+    use super::*;
+
+    fn capture(layer: RateLimitLayer, log: impl FnOnce()) -> String {
+        let writer = TestWriter::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(layer)
+            .with(fmt_layer(LogFormat::Compact, writer.clone(), plain()));
+        tracing::subscriber::with_default(subscriber, log);
+        writer.output()
+    }
+
+    /// Test that each callsite is limited separately and that suppressed events are summarized.
+    #[test]
+    fn test_rate_limit_per_callsite() {
+        let config = RateLimitConfig {
+            max_events: 3,
+            // Long enough for the background reporter never to run during the test.
+            interval: Duration::from_secs(3600),
+            ..RateLimitConfig::default()
+        };
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let clock = now.clone();
+        let layer = RateLimitLayer::new(config).unwrap().with_clock(move || *clock.lock().unwrap());
+        let hot_path = |i: usize| tracing::error!("hot path {}", i);
+        let output = capture(layer.clone(), || {
+            for i in 0..10 {
+                hot_path(i);
+                if i % 5 == 0 {
+                    tracing::error!("other path {}", i);
+                }
+            }
+            layer.report();
+            *now.lock().unwrap() += Duration::from_secs(3600);
+            hot_path(10);
+            layer.report();
+        });
+
+        assert_eq!(output.matches("hot path").count(), 4, "{}", output);
+        assert!(output.contains("hot path 10"), "{}", output);
+        assert_eq!(output.matches("other path").count(), 2, "{}", output);
+        assert_eq!(output.matches(" WARN ").count(), 1, "{}", output);
+        assert!(output.contains("suppressed 7 events from multitool_hg::logger::rate_limit::tests"), "{}", output);
+    }
+
+    /// Test that debug events are sampled at the configured ratio.
+    #[test]
+    fn test_sampling() {
+        let config = RateLimitConfig {
+            max_events: u32::MAX,
+            debug_sample_ratio: 0.5,
+            trace_sample_ratio: 0.0,
+            ..RateLimitConfig::default()
+        };
+        let output = capture(RateLimitLayer::new(config).unwrap().with_seed(42), || {
+            for _ in 0..1000 {
+                tracing::debug!("sampled");
+                tracing::trace!("dropped");
+                tracing::info!("kept");
+            }
+        });
+
+        // The generator is seeded, so the draw is the same on every run.
+        assert_eq!(output.matches("sampled").count(), 496);
+        assert_eq!(output.matches("dropped").count(), 0);
+        assert_eq!(output.matches("kept").count(), 1000);
+    }
+
+    /// Test that invalid configurations are rejected.
+    #[test]
+    fn test_check() {
+        assert!(RateLimitConfig::default().check().is_ok());
+        assert!(RateLimitConfig { max_events: 0, ..RateLimitConfig::default() }.check().is_err());
+        assert!(RateLimitConfig { debug_sample_ratio: 2.0, ..RateLimitConfig::default() }.check().is_err());
+    }
+}
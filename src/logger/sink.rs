@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::logger::file::FileLogConfig;
+use crate::logger::journald::JournaldConfig;
+use crate::logger::syslog::SyslogConfig;
+use crate::logger::tracer_logger::{LogDirectives, LogFormat, LogLevel};
+
+/// `SinkDestination` defines where a sink writes its log events.
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// destination: stderr
+/// ```
+///
+/// ```yaml
+/// destination:
+///   file:
+///     path: /var/log/my-service/service.log
+/// ```
+///
+/// ```yaml
+/// destination:
+///   syslog:
+///     transport:
+///       udp: 127.0.0.1:514
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SinkDestination {
+    /// The standard output of the process.
+    Stdout,
+    /// The standard error of the process.
+    Stderr,
+    /// A rotating log file.
+    File(FileLogConfig),
+    /// The systemd journal; requires the `logger-journald` feature.
+    /// The sink's format is ignored, as journald stores events as structured entries.
+    Journald(JournaldConfig),
+    /// The local syslog daemon, as RFC 5424 messages; requires the `logger-syslog` feature.
+    /// The sink's format is ignored.
+    Syslog(SyslogConfig),
+}
+
+/// `Sink` is one output of the logger, with its own destination, format and filtering.
+///
+/// A logger can write to several sinks at once, for example human-readable logs on
+/// stderr at `info` and JSON at `debug` to a file. Settings that a sink does not
+/// specify are taken from the [`LoggerBuilder`](crate::logger::builder::LoggerBuilder),
+/// and a sink without its own level or directives follows the logger's defaults when
+/// they are changed at runtime through the [`LoggerHandle`](crate::logger::handle::LoggerHandle).
+///
+/// # Example
+/// ```rust
+/// use multitool_hg::logger::builder::LoggerBuilder;
+/// use multitool_hg::logger::file::FileLogConfig;
+/// use multitool_hg::logger::sink::Sink;
+/// use multitool_hg::logger::tracer_logger::{LogFormat, LogLevel};
+///
+/// fn main() {
+///     let log_file = std::env::temp_dir().join("service.log");
+///     let _guard = LoggerBuilder::new()
+///         .level(LogLevel::Info)
+///         .sink(Sink::stderr().format(LogFormat::Compact))
+///         .sink(Sink::file(FileLogConfig::new(log_file)).format(LogFormat::Json).level(LogLevel::Debug))
+///         .init();
+/// }
+/// ```
+///
+/// The same sinks in a YAML configuration:
+///
+/// ```yaml
+/// - destination: stderr
+///   format: compact
+/// - destination:
+///     file:
+///       path: /tmp/service.log
+///   format: json
+///   level: debug
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Sink {
+    /// Where the sink writes its events.
+    pub destination: SinkDestination,
+    /// The output format; if not set, the logger's format is used.
+    #[serde(default)]
+    pub format: Option<LogFormat>,
+    /// The level of this sink; if not set, the logger's level is used.
+    #[serde(default)]
+    pub level: Option<LogLevel>,
+    /// The filter directives of this sink; if not set, the logger's directives are used.
+    #[serde(default)]
+    pub directives: Option<LogDirectives>,
+}
+
+impl Sink {
+    /// Creates a sink writing to `destination` with the logger's format and filtering.
+    pub fn new(destination: SinkDestination) -> Self {
+        Sink {
+            destination,
+            format: None,
+            level: None,
+            directives: None,
+        }
+    }
+
+    /// Creates a sink writing to the standard output.
+    pub fn stdout() -> Self {
+        Self::new(SinkDestination::Stdout)
+    }
+
+    /// Creates a sink writing to the standard error.
+    pub fn stderr() -> Self {
+        Self::new(SinkDestination::Stderr)
+    }
+
+    /// Creates a sink writing to a rotating log file.
+    pub fn file(file: FileLogConfig) -> Self {
+        Self::new(SinkDestination::File(file))
+    }
+
+    /// Creates a sink writing to the systemd journal.
+    pub fn journald(journald: JournaldConfig) -> Self {
+        Self::new(SinkDestination::Journald(journald))
+    }
+
+    /// Creates a sink writing to the local syslog daemon.
+    pub fn syslog(syslog: SyslogConfig) -> Self {
+        Self::new(SinkDestination::Syslog(syslog))
+    }
+
+    /// Sets the output format of this sink.
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Sets the level of this sink, independently of the logger's level.
+    pub fn level(mut self, level: LogLevel) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Sets the filter directives of this sink, independently of the logger's directives.
+    pub fn directives(mut self, directives: LogDirectives) -> Self {
+        self.directives = Some(directives);
+        self
+    }
+}
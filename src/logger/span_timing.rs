@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::dispatcher::WeakDispatch;
+use tracing::span::{Attributes, Id};
+use tracing::{Dispatch, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// `SpanTimingConfig` measures how long spans take and warns about slow ones.
+///
+/// When a span closes, its busy time (spent inside the span) and idle time (between
+/// its creation and closing, while not entered, e.g. an instrumented future waiting on
+/// I/O) are measured. If the total exceeds the threshold for the span's name, or the
+/// default threshold, a warning such as `slow span db_query took 1.2s (threshold 500ms)`
+/// is logged within the span, so its fields are included. With `log_closed`, the
+/// timings of every span are also logged at debug level.
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// default_threshold: 1s
+/// thresholds:
+///   db_query: 200ms
+///   redis_command: 50ms
+/// log_closed: false
+/// ```
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SpanTimingConfig {
+    /// The threshold of spans without a threshold of their own; if not set, such spans never trigger a warning.
+    #[serde(with = "crate::duration::option")]
+    pub default_threshold: Option<Duration>,
+    /// Thresholds by span name, e.g. `200ms`.
+    #[serde(with = "crate::duration::map")]
+    pub thresholds: BTreeMap<String, Duration>,
+    /// Whether the timings of every span are logged at debug level when it closes (default is false).
+    pub log_closed: bool,
+}
+
+impl SpanTimingConfig {
+    /// Sets the threshold of spans without a threshold of their own.
+    pub fn default_threshold(mut self, threshold: Duration) -> Self {
+        self.default_threshold = Some(threshold);
+        self
+    }
+
+    /// Sets the threshold of the spans named `name`.
+    pub fn threshold(mut self, name: impl Into<String>, threshold: Duration) -> Self {
+        self.thresholds.insert(name.into(), threshold);
+        self
+    }
+
+    /// Checks the validity of the configuration.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the configuration is valid, or an `anyhow::Error` describing the problem.
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.default_threshold.is_some_and(|threshold| threshold.is_zero()) {
+            return Err(anyhow::Error::msg("Field `default_threshold` must be greater than zero."));
+        }
+        if let Some((name, _)) = self.thresholds.iter().find(|(_, threshold)| threshold.is_zero()) {
+            return Err(anyhow::Error::msg(format!("Threshold of span `{}` must be greater than zero.", name)));
+        }
+        Ok(())
+    }
+
+    fn threshold_for(&self, name: &str) -> Option<Duration> {
+        self.thresholds.get(name).copied().or(self.default_threshold)
+    }
+}
+
+/// The busy and idle time of a span so far, stored in its extensions.
+struct Timings {
+    busy: Duration,
+    idle: Duration,
+    last: Instant,
+}
+
+/// A layer that logs the timings of closed spans and warns about slow ones.
+///
+/// Its events are dispatched to the subscriber the layer belongs to, with the closing
+/// span as their parent. Clones share their state.
+#[derive(Clone)]
+pub(crate) struct SpanTimingLayer {
+    config: Arc<SpanTimingConfig>,
+    dispatch: Arc<OnceLock<WeakDispatch>>,
+}
+
+impl SpanTimingLayer {
+    pub(crate) fn new(config: SpanTimingConfig) -> anyhow::Result<Self> {
+        config.check()?;
+        Ok(SpanTimingLayer {
+            config: Arc::new(config),
+            dispatch: Arc::new(OnceLock::new()),
+        })
+    }
+}
+
+impl<S> Layer<S> for SpanTimingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_register_dispatch(&self, dispatch: &Dispatch) {
+        let _ = self.dispatch.set(dispatch.downgrade());
+    }
+
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Timings {
+                busy: Duration::ZERO,
+                idle: Duration::ZERO,
+                last: Instant::now(),
+            });
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(timings) = span.extensions_mut().get_mut::<Timings>() {
+                let now = Instant::now();
+                timings.idle += now.saturating_duration_since(timings.last);
+                timings.last = now;
+            }
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(timings) = span.extensions_mut().get_mut::<Timings>() {
+                let now = Instant::now();
+                timings.busy += now.saturating_duration_since(timings.last);
+                timings.last = now;
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(Timings { busy, idle, last }) = span.extensions_mut().remove::<Timings>() else {
+            return;
+        };
+        let idle = idle + last.elapsed();
+        let total = busy + idle;
+        let name = span.name();
+        let threshold = self.config.threshold_for(name).filter(|threshold| total > *threshold);
+        if threshold.is_none() && !self.config.log_closed {
+            return;
+        }
+        let Some(dispatch) = self.dispatch.get().and_then(WeakDispatch::upgrade) else {
+            return;
+        };
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            if let Some(threshold) = threshold {
+                tracing::warn!(
+                    target: "multitool_hg::logger::span_timing",
+                    parent: &id,
+                    { time.busy = ?busy, time.idle = ?idle },
+                    "slow span {} took {:?} (threshold {:?})",
+                    name,
+                    total,
+                    threshold,
+                );
+            } else {
+                tracing::debug!(
+                    target: "multitool_hg::logger::span_timing",
+                    parent: &id,
+                    { time.busy = ?busy, time.idle = ?idle },
+                    "span {} closed after {:?}",
+                    name,
+                    total,
+                );
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use tracing_subscriber::layer::SubscriberExt;
+    use crate::logger::builder::fmt_layer;
+    use crate::logger::builder::tests::{plain, TestWriter};
+    use crate::logger::tracer_logger::LogFormat;
+    use super::*;
+
+    fn capture(config: SpanTimingConfig, log: impl FnOnce()) -> String {
+        let writer = TestWriter::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanTimingLayer::new(config).unwrap())
+            .with(fmt_layer(LogFormat::Compact, writer.clone(), plain()));
+        tracing::subscriber::with_default(subscriber, log);
+        writer.output()
+    }
+
+    /// Test that only spans exceeding their threshold are reported, with their fields.
+    #[test]
+    fn test_slow_spans() {
+        let config = SpanTimingConfig::default()
+            .default_threshold(Duration::from_secs(10))
+            .threshold("db_query", Duration::from_millis(20));
+        let output = capture(config, || {
+            tracing::info_span!("db_query", table = "users").in_scope(|| thread::sleep(Duration::from_millis(30)));
+            tracing::info_span!("db_query", table = "orders").in_scope(|| {});
+            tracing::info_span!("other").in_scope(|| thread::sleep(Duration::from_millis(30)));
+        });
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1, "{}", output);
+        assert!(lines[0].contains("WARN"), "{}", output);
+        assert!(lines[0].contains("db_query: multitool_hg::logger::span_timing: slow span db_query took"), "{}", output);
+        assert!(lines[0].ends_with("table=\"users\""), "{}", output);
+        assert!(lines[0].contains("(threshold 20ms)"), "{}", output);
+        assert!(lines[0].contains("time.busy="), "{}", output);
+    }
+
+    /// Test that busy and idle time are measured separately and logged on close.
+    #[test]
+    fn test_log_closed() {
+        let config = SpanTimingConfig {
+            log_closed: true,
+            ..SpanTimingConfig::default()
+        };
+        let output = capture(config, || {
+            let span = tracing::info_span!("request");
+            span.in_scope(|| thread::sleep(Duration::from_millis(20)));
+            thread::sleep(Duration::from_millis(40));
+        });
+
+        assert!(output.contains("DEBUG"), "{}", output);
+        assert!(output.contains("request: multitool_hg::logger::span_timing: span request closed after"), "{}", output);
+        let millis = |field: &str| -> f64 {
+            let value = output.split(field).nth(1).unwrap().split(' ').next().unwrap().trim_end();
+            match value.strip_suffix("ms") {
+                Some(millis) => millis.parse().unwrap(),
+                None => value.strip_suffix('s').unwrap().parse::<f64>().unwrap() * 1000.0,
+            }
+        };
+        // Sleeps may overrun on a loaded machine, so only the lower bounds are reliable.
+        let (busy, idle) = (millis("time.busy="), millis("time.idle="));
+        assert!(busy >= 20.0 && idle >= 40.0, "{}", output);
+    }
+
+    /// Test that zero thresholds are rejected.
+    #[test]
+    fn test_check() {
+        assert!(SpanTimingConfig::default().check().is_ok());
+        assert!(SpanTimingConfig::default().default_threshold(Duration::ZERO).check().is_err());
+        assert!(SpanTimingConfig::default().threshold("db_query", Duration::ZERO).check().is_err());
+    }
+}
@@ -0,0 +1,407 @@
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// `SyslogTransport` defines how messages reach the local syslog daemon.
+///
+/// Unix sockets are only available on Unix targets; elsewhere, only UDP is supported.
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// transport:
+///   unix: /dev/log
+/// ```
+///
+/// ```yaml
+/// transport:
+///   udp: 127.0.0.1:514
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyslogTransport {
+    /// A Unix datagram socket, usually `/dev/log`.
+    #[cfg(unix)]
+    Unix(PathBuf),
+    /// UDP to the given address, usually `127.0.0.1:514`.
+    Udp(String),
+}
+
+impl Default for SyslogTransport {
+    /// Returns the Unix socket `/dev/log` on Unix targets, and UDP to `127.0.0.1:514` elsewhere.
+    fn default() -> Self {
+        #[cfg(unix)]
+        return SyslogTransport::Unix(PathBuf::from("/dev/log"));
+        #[cfg(not(unix))]
+        return SyslogTransport::Udp("127.0.0.1:514".to_string());
+    }
+}
+
+/// `SyslogFacility` is the RFC 5424 facility that messages are logged under.
+#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyslogFacility {
+    /// Messages generated by user-level programs.
+    User,
+    /// Messages generated by system daemons.
+    #[default]
+    Daemon,
+    /// Locally defined facilities 0 to 7.
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+#[cfg(feature = "logger-syslog")]
+impl SyslogFacility {
+    fn code(self) -> u8 {
+        match self {
+            SyslogFacility::User => 1,
+            SyslogFacility::Daemon => 3,
+            SyslogFacility::Local0 => 16,
+            SyslogFacility::Local1 => 17,
+            SyslogFacility::Local2 => 18,
+            SyslogFacility::Local3 => 19,
+            SyslogFacility::Local4 => 20,
+            SyslogFacility::Local5 => 21,
+            SyslogFacility::Local6 => 22,
+            SyslogFacility::Local7 => 23,
+        }
+    }
+}
+
+/// `SyslogConfig` describes a sink that sends RFC 5424 messages to the local syslog daemon.
+///
+/// Writing to syslog requires the `logger-syslog` feature. Levels are mapped to
+/// severities like journald does: `error` to err (3), `warn` to warning (4), `info`
+/// to notice (5), `debug` to info (6) and `trace` to debug (7). The event's target
+/// and fields are sent as structured data. Messages are sent from a background thread.
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// transport:
+///   udp: 127.0.0.1:514
+/// facility: local0
+/// app_name: my-service
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SyslogConfig {
+    /// Where messages are sent (default is the Unix socket `/dev/log`, or UDP to `127.0.0.1:514` on non-Unix targets).
+    #[serde(default)]
+    pub transport: SyslogTransport,
+    /// The facility that messages are logged under (default is daemon).
+    #[serde(default)]
+    pub facility: SyslogFacility,
+    /// The APP-NAME of the messages; defaults to the name of the executable.
+    #[serde(default)]
+    pub app_name: Option<String>,
+}
+
+impl SyslogConfig {
+    /// Creates a configuration for the given transport with the default facility and app name.
+    pub fn new(transport: SyslogTransport) -> Self {
+        SyslogConfig {
+            transport,
+            ..SyslogConfig::default()
+        }
+    }
+
+    /// Checks the validity of the configuration.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the configuration is valid, or an `anyhow::Error` describing the problem.
+    pub fn check(&self) -> anyhow::Result<()> {
+        match &self.transport {
+            #[cfg(unix)]
+            SyslogTransport::Unix(path) if path.as_os_str().is_empty() => {
+                Err(anyhow::Error::msg("Field `transport.unix` must not be empty."))
+            }
+            SyslogTransport::Udp(address) if address.is_empty() => {
+                Err(anyhow::Error::msg("Field `transport.udp` must not be empty."))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Opens the socket and starts the background writer.
+    #[cfg(feature = "logger-syslog")]
+    pub(crate) fn open(&self) -> anyhow::Result<(SyslogLayer, crate::logger::writer::NonBlocking)> {
+        SyslogLayer::open(self)
+    }
+}
+
+#[cfg(feature = "logger-syslog")]
+pub(crate) use layer::SyslogLayer;
+
+#[cfg(feature = "logger-syslog")]
+mod layer {
+    use std::fmt::{self, Write as _};
+    use std::io::{self, Write};
+    use std::net::UdpSocket;
+    #[cfg(unix)]
+    use std::os::unix::net::UnixDatagram;
+    use std::sync::Arc;
+
+    use tracing::field::{Field, Visit};
+    use tracing::{Event, Level, Subscriber};
+    use tracing_log::NormalizeEvent;
+    use tracing_subscriber::layer::{Context, Layer};
+
+    use crate::logger::redact::{RedactingVisitor, Redactor};
+    use crate::logger::writer::NonBlocking;
+    use super::{SyslogConfig, SyslogTransport};
+
+    /// The SD-ID of the structured data element carrying the event's fields. 32473 is
+    /// the private enterprise number reserved for documentation (RFC 5612).
+    const SD_ID: &str = "fields@32473";
+
+    /// Sends every write as one datagram.
+    enum Socket {
+        #[cfg(unix)]
+        Unix(UnixDatagram),
+        Udp(UdpSocket),
+    }
+
+    impl Write for Socket {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            match self {
+                #[cfg(unix)]
+                Socket::Unix(socket) => socket.send(buf),
+                Socket::Udp(socket) => socket.send(buf),
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A layer formatting events as RFC 5424 messages.
+    pub(crate) struct SyslogLayer {
+        writer: NonBlocking,
+        facility: u8,
+        hostname: String,
+        app_name: String,
+        proc_id: u32,
+        redactor: Option<Arc<Redactor>>,
+    }
+
+    impl SyslogLayer {
+        pub(super) fn open(config: &SyslogConfig) -> anyhow::Result<(Self, NonBlocking)> {
+            let socket = match &config.transport {
+                #[cfg(unix)]
+                SyslogTransport::Unix(path) => {
+                    let socket = UnixDatagram::unbound()?;
+                    socket
+                        .connect(path)
+                        .map_err(|err| anyhow::Error::msg(format!("Failed to connect to syslog socket `{}`: {}", path.display(), err)))?;
+                    Socket::Unix(socket)
+                }
+                SyslogTransport::Udp(address) => {
+                    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+                    socket
+                        .connect(address)
+                        .map_err(|err| anyhow::Error::msg(format!("Failed to connect to syslog address `{}`: {}", address, err)))?;
+                    Socket::Udp(socket)
+                }
+            };
+            let writer = NonBlocking::new(socket)?;
+
+            let app_name = config.app_name.clone().unwrap_or_else(|| {
+                std::env::current_exe()
+                    .ok()
+                    .and_then(|exe| exe.file_name().map(|name| name.to_string_lossy().into_owned()))
+                    .unwrap_or_else(|| "-".to_string())
+            });
+            let layer = SyslogLayer {
+                writer: writer.clone(),
+                facility: config.facility.code(),
+                hostname: header_field(&hostname(), 255),
+                app_name: header_field(&app_name, 48),
+                proc_id: std::process::id(),
+                redactor: None,
+            };
+            Ok((layer, writer))
+        }
+
+        /// Masks field values with `redactor` before they are sent.
+        pub(crate) fn with_redactor(mut self, redactor: Option<Arc<Redactor>>) -> Self {
+            self.redactor = redactor;
+            self
+        }
+
+        /// Formats `event` as an RFC 5424 message.
+        fn format(&self, event: &Event<'_>) -> String {
+            let normalized = event.normalized_metadata();
+            let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+            let severity = match *metadata.level() {
+                Level::ERROR => 3,
+                Level::WARN => 4,
+                Level::INFO => 5,
+                Level::DEBUG => 6,
+                Level::TRACE => 7,
+            };
+
+            let mut fields = StructuredData::default();
+            fields.param("target", metadata.target());
+            match &self.redactor {
+                Some(redactor) => event.record(&mut RedactingVisitor::new(&mut fields, redactor.clone())),
+                None => event.record(&mut fields),
+            }
+
+            format!(
+                "<{}>1 {} {} {} {} - [{}{}] {}",
+                self.facility * 8 + severity,
+                chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+                self.hostname,
+                self.app_name,
+                self.proc_id,
+                SD_ID,
+                fields.params,
+                fields.message,
+            )
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SyslogLayer {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let _ = self.writer.clone().write_all(self.format(event).as_bytes());
+        }
+    }
+
+    /// Returns the name of the host from the kernel on Linux, or from the `HOSTNAME` or
+    /// `COMPUTERNAME` environment variables elsewhere, or an empty string if none is set.
+    fn hostname() -> String {
+        std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .ok()
+            .into_iter()
+            .chain(["HOSTNAME", "COMPUTERNAME"].into_iter().filter_map(|name| std::env::var(name).ok()))
+            .map(|hostname| hostname.trim().to_string())
+            .find(|hostname| !hostname.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// Replaces characters that are not allowed in a header field and truncates it,
+    /// using the NILVALUE `-` for empty fields.
+    fn header_field(value: &str, max_len: usize) -> String {
+        let value: String = value
+            .chars()
+            .map(|c| if c.is_ascii_graphic() { c } else { '_' })
+            .take(max_len)
+            .collect();
+        if value.is_empty() {
+            "-".to_string()
+        } else {
+            value
+        }
+    }
+
+    /// Collects the message and the SD-PARAMs of an event.
+    #[derive(Default)]
+    struct StructuredData {
+        message: String,
+        params: String,
+    }
+
+    impl StructuredData {
+        fn param(&mut self, name: &str, value: &str) {
+            let name: String = name
+                .chars()
+                .map(|c| if c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"') { c } else { '_' })
+                .take(32)
+                .collect();
+            let _ = write!(self.params, " {}=\"", name);
+            for c in value.chars() {
+                if matches!(c, '"' | '\\' | ']') {
+                    self.params.push('\\');
+                }
+                self.params.push(c);
+            }
+            self.params.push('"');
+        }
+    }
+
+    impl Visit for StructuredData {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.message = value.to_string();
+            } else if !field.name().starts_with("log.") {
+                self.param(field.name(), value);
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.record_str(field, &format!("{:?}", value));
+        }
+    }
+
+    impl Visit for &mut StructuredData {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            (**self).record_str(field, value);
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            (**self).record_debug(field, value);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "logger-syslog"))]
+mod tests {
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    use tracing_subscriber::layer::SubscriberExt;
+    use super::*;
+
+    /// Test that events are sent as RFC 5424 messages with structured data.
+    #[test]
+    fn test_udp_messages() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let config = SyslogConfig {
+            transport: SyslogTransport::Udp(server.local_addr().unwrap().to_string()),
+            facility: SyslogFacility::Local0,
+            app_name: Some("my service".to_string()),
+        };
+        let (layer, writer) = config.open().unwrap();
+
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::warn!(user_id = 5, query = "a \"quoted\" value]", "disk almost full");
+            tracing::debug!("details");
+        });
+        writer.sync();
+
+        let mut buf = [0; 2048];
+        let len = server.recv(&mut buf).unwrap();
+        let message = String::from_utf8_lossy(&buf[..len]).into_owned();
+        assert!(message.starts_with("<132>1 "), "{}", message);
+        assert!(message.contains(&format!(" my_service {} - ", std::process::id())), "{}", message);
+        assert!(
+            message.ends_with(
+                r#"[fields@32473 target="multitool_hg::logger::syslog::tests" user_id="5" query="a \"quoted\" value\]"] disk almost full"#
+            ),
+            "{}",
+            message
+        );
+
+        let len = server.recv(&mut buf).unwrap();
+        assert!(String::from_utf8_lossy(&buf[..len]).starts_with("<134>1 "));
+    }
+
+    /// Test that connecting to a missing Unix socket fails.
+    #[cfg(unix)]
+    #[test]
+    fn test_missing_socket() {
+        let config = SyslogConfig::new(SyslogTransport::Unix(PathBuf::from("/nonexistent/syslog.sock")));
+        assert!(config.open().is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::FormatTime;
+
+/// `TimestampFormat` defines how the timestamps of log events are rendered.
+///
+/// Example configuration in YAML:
+///
+/// ```yaml
+/// timestamp_format: local
+/// ```
+///
+/// ```yaml
+/// timestamp_format:
+///   custom: "%Y-%m-%d %H:%M:%S%.3f"
+/// ```
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampFormat {
+    /// RFC 3339 in UTC with microseconds, e.g. `2024-05-01T12:30:00.123456Z`.
+    #[default]
+    Rfc3339Utc,
+    /// RFC 3339 in the local time zone with its offset, e.g. `2024-05-01T14:30:00.123456+02:00`.
+    Local,
+    /// A `strftime`-style format string applied to the local time, e.g. `%Y-%m-%d %H:%M:%S%.3f`.
+    /// See `chrono::format::strftime` for the supported specifiers.
+    Custom(String),
+    /// The time elapsed since the logger was initialized, e.g. `12.345678s`.
+    Uptime,
+}
+
+impl TimestampFormat {
+    /// Checks the validity of the format.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the format is valid, or an `anyhow::Error` describing the problem.
+    pub fn check(&self) -> anyhow::Result<()> {
+        if let TimestampFormat::Custom(format) = self {
+            chrono::format::StrftimeItems::new(format)
+                .parse()
+                .map_err(|err| anyhow::Error::msg(format!("Invalid timestamp format `{}`: {}", format, err)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders event timestamps in a [`TimestampFormat`].
+#[derive(Clone, Debug)]
+pub(crate) struct Timer {
+    format: TimestampFormat,
+    start: Instant,
+}
+
+impl Timer {
+    pub(crate) fn new(format: TimestampFormat) -> Self {
+        Timer {
+            format,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl FormatTime for Timer {
+    fn format_time(&self, w: &mut Writer<'_>) -> fmt::Result {
+        use chrono::SecondsFormat;
+
+        match &self.format {
+            TimestampFormat::Rfc3339Utc => write!(w, "{}", chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true)),
+            TimestampFormat::Local => write!(w, "{}", chrono::Local::now().to_rfc3339_opts(SecondsFormat::Micros, false)),
+            TimestampFormat::Custom(format) => write!(w, "{}", chrono::Local::now().format(format)),
+            TimestampFormat::Uptime => {
+                let elapsed = self.start.elapsed();
+                write!(w, "{}.{:06}s", elapsed.as_secs(), elapsed.subsec_micros())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(format: TimestampFormat) -> String {
+        let mut output = String::new();
+        Timer::new(format).format_time(&mut Writer::new(&mut output)).unwrap();
+        output
+    }
+
+    /// Test rendering every timestamp format.
+    #[test]
+    fn test_formats() {
+        let utc = render(TimestampFormat::Rfc3339Utc);
+        assert!(chrono::DateTime::parse_from_rfc3339(&utc).is_ok(), "{}", utc);
+        assert!(utc.ends_with('Z'), "{}", utc);
+
+        let local = render(TimestampFormat::Local);
+        assert!(chrono::DateTime::parse_from_rfc3339(&local).is_ok(), "{}", local);
+
+        let custom = render(TimestampFormat::Custom("%Y/%m/%d".to_string()));
+        assert_eq!(custom, chrono::Local::now().format("%Y/%m/%d").to_string());
+
+        let uptime = render(TimestampFormat::Uptime);
+        assert!(uptime.starts_with("0.") && uptime.ends_with('s'), "{}", uptime);
+    }
+
+    /// Test that malformed custom formats are rejected.
+    #[test]
+    fn test_check() {
+        assert!(TimestampFormat::Custom("%Y-%m-%d %H:%M".to_string()).check().is_ok());
+        assert!(TimestampFormat::Custom("%Y-%Q".to_string()).check().is_err());
+    }
+}
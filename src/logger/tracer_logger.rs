@@ -1,61 +1,331 @@
-use tracing_subscriber::filter::LevelFilter;
-use tracing_subscriber::fmt;
-use serde::Serialize;
+use tracing::Level;
+use tracing_subscriber::filter::{Directive, EnvFilter, LevelFilter};
+use serde::{Deserialize, Serialize};
+use crate::logger::builder::LoggerBuilder;
+use crate::logger::config::LoggerConfig;
+use crate::logger::guard::LoggerGuard;
 
 /// `LogLevel` defines the different levels of logging that can be used
 /// within the application. These levels correspond to the common logging
 /// levels found in Rust's logging libraries.
 ///
-/// This enum supports deserialization via Serde and is compatible with
-/// command-line arguments using Clap.
-#[derive(clap::ValueEnum, Clone, Default, Debug, Serialize, PartialEq)]
+/// Levels are ordered by severity: `Trace < Debug < Info < Warn < Error < Off`, so
+/// `level >= LogLevel::Warn` holds for warnings and errors. This is the reverse of the
+/// order of `log::LevelFilter` and `tracing`'s `LevelFilter`. A sink at level `l` logs
+/// the events whose level is at least as severe as `l`, so `Off` logs nothing.
+///
+/// This enum supports (de)serialization via Serde and is compatible with
+/// command-line arguments using Clap. Serde, Clap and [`FromStr`](std::str::FromStr)
+/// accept the same names and aliases, ignoring case. It converts to and from the levels
+/// and level filters of `tracing` and `log`.
+#[derive(clap::ValueEnum, Clone, Copy, Default, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[serde(rename_all = "kebab-case")]
 pub enum LogLevel {
-    /// Log level for informational messages.
-    #[default]
-    Info,
     /// Log level for detailed trace-level messages.
+    #[value(alias = "5")]
     Trace,
     /// Log level for debugging messages.
+    #[value(alias = "4")]
     Debug,
+    /// Log level for informational messages.
+    #[default]
+    #[value(alias = "3")]
+    Info,
     /// Log level for warnings.
+    #[value(alias = "warning", alias = "2")]
     Warn,
     /// Log level for errors.
+    #[value(alias = "err", alias = "1")]
     Error,
+    /// Disables logging.
+    #[value(alias = "0")]
+    Off,
 }
 
 impl std::str::FromStr for LogLevel {
     type Err = String;
 
     /// Converts a string into a `LogLevel`. The string is case-insensitive and
-    /// should match one of the log levels: info, trace, debug, warn, or error.
+    /// should match one of the log levels: off, error, warn, info, debug, or trace.
+    /// The aliases `err` and `warning` are accepted, as are the numeric verbosities
+    /// `0` (off) to `5` (trace).
     ///
     /// # Errors
     /// Returns an error if the input string does not match a valid log level.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "info" => Ok(LogLevel::Info),
-            "trace" => Ok(LogLevel::Trace),
-            "debug" => Ok(LogLevel::Debug),
-            "warn" => Ok(LogLevel::Warn),
-            "error" => Ok(LogLevel::Error),
+        match s.trim().to_lowercase().as_str() {
+            "off" | "0" => Ok(LogLevel::Off),
+            "error" | "err" | "1" => Ok(LogLevel::Error),
+            "warn" | "warning" | "2" => Ok(LogLevel::Warn),
+            "info" | "3" => Ok(LogLevel::Info),
+            "debug" | "4" => Ok(LogLevel::Debug),
+            "trace" | "5" => Ok(LogLevel::Trace),
             _ => Err(format!("Invalid log level: {}", s)),
         }
     }
 }
 
+impl<'de> Deserialize<'de> for LogLevel {
+    /// Deserializes a level from any string accepted by [`FromStr`](std::str::FromStr),
+    /// or from a numeric verbosity between `0` (off) and `5` (trace).
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct LevelVisitor;
+
+        impl serde::de::Visitor<'_> for LevelVisitor {
+            type Value = LogLevel;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "a log level such as `info`, or a verbosity between 0 and 5")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, s: &str) -> Result<LogLevel, E> {
+                s.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, verbosity: u64) -> Result<LogLevel, E> {
+                self.visit_str(&verbosity.to_string())
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, verbosity: i64) -> Result<LogLevel, E> {
+                self.visit_str(&verbosity.to_string())
+            }
+        }
+
+        deserializer.deserialize_any(LevelVisitor)
+    }
+}
+
 impl std::fmt::Display for LogLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            LogLevel::Off => write!(f, "off"),
+            LogLevel::Error => write!(f, "error"),
+            LogLevel::Warn => write!(f, "warn"),
             LogLevel::Info => write!(f, "info"),
-            LogLevel::Trace => write!(f, "trace"),
             LogLevel::Debug => write!(f, "debug"),
-            LogLevel::Warn => write!(f, "warn"),
-            LogLevel::Error => write!(f, "error"),
+            LogLevel::Trace => write!(f, "trace"),
+        }
+    }
+}
+
+impl From<Level> for LogLevel {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warn,
+            Level::INFO => LogLevel::Info,
+            Level::DEBUG => LogLevel::Debug,
+            Level::TRACE => LogLevel::Trace,
+        }
+    }
+}
+
+impl TryFrom<LogLevel> for Level {
+    type Error = String;
+
+    /// Converts a `LogLevel` into a `tracing` level.
+    ///
+    /// # Errors
+    /// Returns an error for `LogLevel::Off`, which has no corresponding level.
+    fn try_from(level: LogLevel) -> Result<Self, Self::Error> {
+        match level {
+            LogLevel::Off => Err("Log level `off` has no corresponding tracing level".to_string()),
+            LogLevel::Error => Ok(Level::ERROR),
+            LogLevel::Warn => Ok(Level::WARN),
+            LogLevel::Info => Ok(Level::INFO),
+            LogLevel::Debug => Ok(Level::DEBUG),
+            LogLevel::Trace => Ok(Level::TRACE),
+        }
+    }
+}
+
+impl From<LevelFilter> for LogLevel {
+    fn from(filter: LevelFilter) -> Self {
+        filter.into_level().map_or(LogLevel::Off, LogLevel::from)
+    }
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+impl From<log::Level> for LogLevel {
+    fn from(level: log::Level) -> Self {
+        LogLevel::from(level.to_level_filter())
+    }
+}
+
+impl TryFrom<LogLevel> for log::Level {
+    type Error = String;
+
+    /// Converts a `LogLevel` into a `log` level.
+    ///
+    /// # Errors
+    /// Returns an error for `LogLevel::Off`, which has no corresponding level.
+    fn try_from(level: LogLevel) -> Result<Self, String> {
+        log::LevelFilter::from(level)
+            .to_level()
+            .ok_or_else(|| "Log level `off` has no corresponding log level".to_string())
+    }
+}
+
+impl From<log::LevelFilter> for LogLevel {
+    fn from(filter: log::LevelFilter) -> Self {
+        match filter {
+            log::LevelFilter::Off => LogLevel::Off,
+            log::LevelFilter::Error => LogLevel::Error,
+            log::LevelFilter::Warn => LogLevel::Warn,
+            log::LevelFilter::Info => LogLevel::Info,
+            log::LevelFilter::Debug => LogLevel::Debug,
+            log::LevelFilter::Trace => LogLevel::Trace,
+        }
+    }
+}
+
+impl From<LogLevel> for log::LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => log::LevelFilter::Off,
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// `LogDirectives` holds `RUST_LOG`-style filter directives, such as
+/// `info,my_service=debug,sqlx=warn`.
+///
+/// Directives are layered on top of the configured `LogLevel`: the level sets the
+/// default for every target, and each directive overrides it for the targets (or
+/// spans) it names. A bare level in the directives replaces the default level.
+///
+/// The directives are validated when parsed, so a `LogDirectives` value is always
+/// well-formed. It (de)serializes as a plain string and can be used as a Clap argument.
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct LogDirectives(String);
+
+impl LogDirectives {
+    /// Returns the parsed directives.
+    fn directives(&self) -> impl Iterator<Item = Directive> + '_ {
+        self.0
+            .split(',')
+            .filter(|part| !part.is_empty())
+            .map(|part| part.parse().expect("directives are validated on parse"))
+    }
+}
+
+impl std::str::FromStr for LogDirectives {
+    type Err = String;
+
+    /// Parses a comma-separated list of filter directives. Whitespace around each
+    /// directive and empty entries are ignored.
+    ///
+    /// # Errors
+    /// Returns an error naming the first malformed directive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            part.parse::<Directive>()
+                .map_err(|err| format!("Invalid log directive `{}`: {}", part, err))?;
+            parts.push(part);
         }
+        Ok(LogDirectives(parts.join(",")))
     }
 }
 
+impl TryFrom<String> for LogDirectives {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<LogDirectives> for String {
+    fn from(value: LogDirectives) -> Self {
+        value.0
+    }
+}
+
+impl std::fmt::Display for LogDirectives {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Builds the filter that enables events at `level` or above, refined by `directives`.
+pub(crate) fn env_filter(level: &LogLevel, directives: Option<&LogDirectives>) -> EnvFilter {
+    let filter = EnvFilter::default().add_directive(LevelFilter::from(*level).into());
+    directives
+        .into_iter()
+        .flat_map(LogDirectives::directives)
+        .fold(filter, EnvFilter::add_directive)
+}
+
+/// `LogFormat` defines how log events are rendered by the logger.
+///
+/// The human-readable formats (`Compact`, `Pretty` and `Full`) are meant for local
+/// development, while `Json` emits one JSON object per line for log pipelines.
+///
+/// This enum supports (de)serialization via Serde and is compatible with
+/// command-line arguments using Clap.
+#[derive(clap::ValueEnum, Clone, Copy, Default, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// Single-line output with span fields appended to the event.
+    #[default]
+    Compact,
+    /// Multi-line, indented output that is easy to read in a terminal.
+    Pretty,
+    /// Single-line output that includes the full span context before the event.
+    Full,
+    /// Newline-delimited JSON with event fields flattened into the top-level object
+    /// and the current span and span list included.
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    /// Converts a string into a `LogFormat`. The string is case-insensitive and
+    /// should match one of the formats: compact, pretty, full, or json.
+    ///
+    /// # Errors
+    /// Returns an error if the input string does not match a valid log format.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "compact" => Ok(LogFormat::Compact),
+            "pretty" => Ok(LogFormat::Pretty),
+            "full" => Ok(LogFormat::Full),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("Invalid log format: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Compact => write!(f, "compact"),
+            LogFormat::Pretty => write!(f, "pretty"),
+            LogFormat::Full => write!(f, "full"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
 
 /// Initializes a new tracing-based logger with the provided `LogLevel`.
 ///
@@ -67,6 +337,12 @@ impl std::fmt::Display for LogLevel {
 /// - `log_level`: The level of logging that should be used. This can be one of
 ///   `Info`, `Debug`, `Error`, `Warn`, or `Trace`.
 ///
+/// Use [`LoggerBuilder`] to select another output format such as JSON.
+///
+/// Calling this function more than once does nothing. If the logger cannot be
+/// installed, for example because a global subscriber has been set by other means,
+/// the error is printed to stderr; use [`LoggerBuilder::try_init`] to handle it instead.
+///
 /// # Example
 /// ```rust
 /// use multitool_hg::logger::tracer_logger::{LogLevel, new_tracer_logger};
@@ -79,25 +355,42 @@ impl std::fmt::Display for LogLevel {
 /// }
 /// ```
 pub fn new_tracer_logger(log_level: LogLevel) {
-    let log_level_filter = match log_level {
-        LogLevel::Trace => LevelFilter::TRACE,
-        LogLevel::Debug => LevelFilter::DEBUG,
-        LogLevel::Info => LevelFilter::INFO,
-        LogLevel::Warn => LevelFilter::WARN,
-        LogLevel::Error => LevelFilter::ERROR,
-    };
-
-    let format = fmt::format()
-        .with_level(true) // include levels in formatted output
-        .with_target(true) // include targets
-        .with_thread_ids(true) // include the thread ID of the current thread
-        .with_thread_names(true) // include the name of the current thread
-        .compact(); // use the `Compact` formatting style.
-
-    tracing_subscriber::fmt()
-        .event_format(format)
-        .with_max_level(log_level_filter)
-        .init();
+    match LoggerBuilder::new().level(log_level).try_init() {
+        // The logger lives for the rest of the process, so its writers must too.
+        Ok(guard) => std::mem::forget(guard),
+        Err(err) => eprintln!("Failed to initialize the logger: {:#}", err),
+    }
+}
+
+/// Initializes a new tracing-based logger from a [`LoggerConfig`], typically loaded
+/// from the service's configuration file.
+///
+/// The configuration is checked before the logger is installed. Like
+/// [`LoggerBuilder::try_init`], calling this function again after a logger has been
+/// installed by this crate is a no-op.
+///
+/// # Errors
+/// Returns an error if the configuration is invalid, if a log file cannot be opened,
+/// or if a global subscriber has already been set by other means.
+///
+/// # Example
+/// ```rust
+/// use multitool_hg::logger::config::LoggerConfig;
+/// use multitool_hg::logger::tracer_logger::{LogLevel, new_tracer_logger_from_config};
+///
+/// fn main() -> anyhow::Result<()> {
+///     let config = LoggerConfig {
+///         level: LogLevel::Debug,
+///         ..LoggerConfig::default()
+///     };
+///     let _guard = new_tracer_logger_from_config(config)?;
+///     log::info!("Hello, world!");
+///     Ok(())
+/// }
+/// ```
+pub fn new_tracer_logger_from_config(config: LoggerConfig) -> anyhow::Result<LoggerGuard> {
+    config.check()?;
+    LoggerBuilder::from(config).try_init()
 }
 
 #[cfg(test)]
@@ -123,11 +416,117 @@ mod tests {
         assert_eq!(LogLevel::Debug.to_string(), "debug");
         assert_eq!(LogLevel::Warn.to_string(), "warn");
         assert_eq!(LogLevel::Error.to_string(), "error");
+        assert_eq!(LogLevel::Off.to_string(), "off");
     }
 
     /// Test parsing invalid log levels from strings.
     #[test]
     fn test_from_str_invalid_level() {
         assert!(LogLevel::from_str("invalid").is_err());
+        assert!(LogLevel::from_str("6").is_err());
+    }
+
+    /// Test parsing the `off` level, aliases and numeric verbosities.
+    #[test]
+    fn test_from_str_aliases() {
+        assert_eq!(LogLevel::from_str("off").unwrap(), LogLevel::Off);
+        assert_eq!(LogLevel::from_str("Warning").unwrap(), LogLevel::Warn);
+        assert_eq!(LogLevel::from_str("err").unwrap(), LogLevel::Error);
+        assert_eq!(LogLevel::from_str("0").unwrap(), LogLevel::Off);
+        assert_eq!(LogLevel::from_str("3").unwrap(), LogLevel::Info);
+        assert_eq!(LogLevel::from_str("5").unwrap(), LogLevel::Trace);
+        assert_eq!(serde_json::from_str::<LogLevel>(r#""warning""#).unwrap(), LogLevel::Warn);
+    }
+
+    /// Test that levels are ordered by severity.
+    #[test]
+    fn test_ordering() {
+        assert!(LogLevel::Trace < LogLevel::Debug);
+        assert!(LogLevel::Debug < LogLevel::Info);
+        assert!(LogLevel::Info < LogLevel::Warn);
+        assert!(LogLevel::Warn < LogLevel::Error);
+        assert!(LogLevel::Error < LogLevel::Off);
+    }
+
+    /// Test that `FromStr`, Serde and Clap accept the same names and aliases.
+    #[test]
+    fn test_aliases_on_every_parser() {
+        let cases = [
+            ("off", LogLevel::Off),
+            ("0", LogLevel::Off),
+            ("ERR", LogLevel::Error),
+            ("1", LogLevel::Error),
+            ("Warning", LogLevel::Warn),
+            ("2", LogLevel::Warn),
+            ("info", LogLevel::Info),
+            ("3", LogLevel::Info),
+            ("4", LogLevel::Debug),
+            ("trace", LogLevel::Trace),
+            ("5", LogLevel::Trace),
+        ];
+        for (input, level) in cases {
+            assert_eq!(LogLevel::from_str(input).unwrap(), level, "{}", input);
+            assert_eq!(serde_json::from_str::<LogLevel>(&format!("{:?}", input)).unwrap(), level, "{}", input);
+            assert_eq!(<LogLevel as clap::ValueEnum>::from_str(input, true).unwrap(), level, "{}", input);
+        }
+        assert_eq!(serde_json::from_str::<LogLevel>("2").unwrap(), LogLevel::Warn);
+        assert!(serde_json::from_str::<LogLevel>("6").is_err());
+        assert_eq!(serde_json::to_string(&LogLevel::Warn).unwrap(), r#""warn""#);
+    }
+
+    /// Test conversions to and from the levels and level filters of `tracing` and `log`.
+    #[test]
+    fn test_conversions() {
+        let levels = [LogLevel::Off, LogLevel::Error, LogLevel::Warn, LogLevel::Info, LogLevel::Debug, LogLevel::Trace];
+        for level in levels {
+            assert_eq!(LogLevel::from(LevelFilter::from(level)), level);
+            assert_eq!(LogLevel::from(log::LevelFilter::from(level)), level);
+            assert_eq!(LevelFilter::from(level).to_string(), level.to_string());
+            assert_eq!(log::LevelFilter::from(level).to_string().to_lowercase(), level.to_string());
+            if let Ok(tracing_level) = Level::try_from(level) {
+                assert_eq!(LogLevel::from(tracing_level), level);
+                assert_eq!(LogLevel::from(log::Level::try_from(level).unwrap()), level);
+            }
+        }
+        assert!(Level::try_from(LogLevel::Off).is_err());
+        assert!(log::Level::try_from(LogLevel::Off).is_err());
+        assert_eq!(LogLevel::from(Level::WARN), LogLevel::Warn);
+    }
+
+    /// Test parsing valid filter directives.
+    #[test]
+    fn test_log_directives_valid() {
+        let directives = LogDirectives::from_str("info, my_service=debug,,sqlx=warn").unwrap();
+        assert_eq!(directives.to_string(), "info,my_service=debug,sqlx=warn");
+        assert_eq!(directives.directives().count(), 3);
+        assert_eq!(LogDirectives::from_str("").unwrap(), LogDirectives::default());
+    }
+
+    /// Test that malformed filter directives are rejected with the offending directive.
+    #[test]
+    fn test_log_directives_invalid() {
+        let err = LogDirectives::from_str("info,sqlx=loud").unwrap_err();
+        assert!(err.contains("sqlx=loud"), "{}", err);
+        assert!(LogDirectives::from_str("my_service[=debug").is_err());
+    }
+
+    /// Test that directives are layered on top of the log level.
+    #[test]
+    fn test_env_filter_layers_directives() {
+        let directives = LogDirectives::from_str("sqlx=warn,my_service=debug").unwrap();
+        let filter = env_filter(&LogLevel::Info, Some(&directives)).to_string();
+        assert!(filter.contains("info"), "{}", filter);
+        assert!(filter.contains("sqlx=warn"), "{}", filter);
+        assert!(filter.contains("my_service=debug"), "{}", filter);
+    }
+
+    /// Test parsing and displaying log formats.
+    #[test]
+    fn test_log_format_round_trip() {
+        for format in [LogFormat::Compact, LogFormat::Pretty, LogFormat::Full, LogFormat::Json] {
+            assert_eq!(LogFormat::from_str(&format.to_string()).unwrap(), format);
+        }
+        assert_eq!(LogFormat::from_str("JSON").unwrap(), LogFormat::Json);
+        assert!(LogFormat::from_str("xml").is_err());
     }
 }
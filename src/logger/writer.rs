@@ -0,0 +1,191 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+
+use tracing_subscriber::fmt::MakeWriter;
+
+/// The number of log lines that may be queued before further lines are dropped.
+const QUEUE_CAPACITY: usize = 128_000;
+
+enum Message {
+    Write(Vec<u8>),
+    Flush(SyncSender<()>),
+}
+
+/// `NonBlocking` hands log lines to a background thread that writes them to the
+/// wrapped writer, so slow I/O never stalls the code that emits events.
+///
+/// Lines are queued in a bounded channel; if the writer falls behind by more than
+/// `QUEUE_CAPACITY` lines, further lines are dropped rather than blocking the caller,
+/// like the lossy mode of `tracing-appender`. Once the background thread has caught
+/// up, it logs a warning such as `dropped 1234 log lines` through the global logger.
+/// The background thread exits once every clone has been dropped.
+#[derive(Clone, Debug)]
+pub(crate) struct NonBlocking {
+    sender: SyncSender<Message>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl NonBlocking {
+    /// Spawns the background thread writing to `writer`.
+    pub(crate) fn new<W: Write + Send + 'static>(writer: W) -> io::Result<Self> {
+        Self::with_capacity(writer, QUEUE_CAPACITY)
+    }
+
+    fn with_capacity<W: Write + Send + 'static>(writer: W, capacity: usize) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let counter = dropped.clone();
+        thread::Builder::new()
+            .name("multitool-log-writer".to_string())
+            .spawn(move || work(writer, receiver, counter))?;
+        Ok(NonBlocking { sender, dropped })
+    }
+
+    /// Blocks until every line queued so far has been written and flushed.
+    pub(crate) fn sync(&self) {
+        let (ack, done) = mpsc::sync_channel(1);
+        if self.sender.send(Message::Flush(ack)).is_ok() {
+            let _ = done.recv();
+        }
+    }
+}
+
+fn work<W: Write>(mut writer: W, receiver: Receiver<Message>, dropped: Arc<AtomicU64>) {
+    let mut reported = 0;
+    while let Ok(message) = receiver.recv() {
+        let mut next = Some(message);
+        // Drain whatever is already queued before flushing, to batch writes.
+        while let Some(message) = next {
+            match message {
+                Message::Write(line) => {
+                    let _ = writer.write_all(&line);
+                }
+                Message::Flush(ack) => {
+                    let _ = writer.flush();
+                    let _ = ack.send(());
+                }
+            }
+            next = receiver.try_recv().ok();
+        }
+        let _ = writer.flush();
+
+        let total = dropped.load(Ordering::Relaxed);
+        if total > reported {
+            tracing::warn!(
+                target: "multitool_hg::logger::writer",
+                dropped = total - reported,
+                "dropped {} log lines because the writer fell behind",
+                total - reported,
+            );
+            reported = total;
+        }
+    }
+}
+
+impl Write for NonBlocking {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.sender.try_send(Message::Write(buf.to_vec())) {
+            Ok(()) => Ok(buf.len()),
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(buf.len())
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "log writer thread has stopped"))
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.sync();
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for NonBlocking {
+    type Writer = NonBlocking;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Test that flushing waits for every queued line to be written.
+    #[test]
+    fn test_flush_writes_queued_lines() {
+        let buffer = SharedBuffer::default();
+        let mut writer = NonBlocking::new(buffer.clone()).unwrap();
+        for i in 0..1000 {
+            writeln!(writer, "line {}", i).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(output.lines().count(), 1000);
+        assert!(output.ends_with("line 999\n"));
+    }
+
+    /// A writer that blocks on its first write until released.
+    struct StalledWriter {
+        entered: mpsc::Sender<()>,
+        release: Receiver<()>,
+        buffer: SharedBuffer,
+    }
+
+    impl Write for StalledWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.entered.send(()).is_ok() {
+                let _ = self.release.recv();
+            }
+            self.buffer.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Test that lines are dropped instead of blocking the caller when the queue is full.
+    #[test]
+    fn test_full_queue_drops_lines() {
+        let buffer = SharedBuffer::default();
+        let (entered, stalled) = mpsc::channel();
+        let (release, released) = mpsc::channel();
+        let writer = StalledWriter { entered, release: released, buffer: buffer.clone() };
+        let mut writer = NonBlocking::with_capacity(writer, 4).unwrap();
+
+        writer.write_all(b"line 0\n").unwrap();
+        // The background thread now holds line 0, so the queue has room for four more.
+        stalled.recv().unwrap();
+        for i in 1..10 {
+            writer.write_all(format!("line {}\n", i).as_bytes()).unwrap();
+        }
+        assert_eq!(writer.dropped.load(Ordering::Relaxed), 5);
+
+        drop(release);
+        writer.flush().unwrap();
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(output, "line 0\nline 1\nline 2\nline 3\nline 4\n");
+    }
+}
@@ -0,0 +1,41 @@
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::{Client, ErrorKind, RedisError};
+
+/// `RedisConnectionManager` is the `bb8` connection manager used by `Rediska`.
+///
+/// It is built from a fully configured `redis::Client`, which allows connections that
+/// cannot be described by a URL alone, such as TLS with custom CA or client certificates.
+#[derive(Debug, Clone)]
+pub struct RedisConnectionManager {
+    client: Client,
+}
+
+impl RedisConnectionManager {
+    /// Creates a new `RedisConnectionManager` that opens connections with the given `client`.
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl bb8::ManageConnection for RedisConnectionManager {
+    type Connection = MultiplexedConnection;
+    type Error = RedisError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        self.client.get_multiplexed_async_connection().await
+    }
+
+    async fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        let pong: String = redis::cmd("PING").query_async(conn).await?;
+        match pong.as_str() {
+            "PONG" => Ok(()),
+            _ => Err((ErrorKind::ResponseError, "ping request").into()),
+        }
+    }
+
+    fn has_broken(&self, _: &mut Self::Connection) -> bool {
+        false
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
+use tokio::time::Instant;
+
+use crate::rediska::store::KeyValueStore;
+
+/// `MemoryStore` is an in-process implementation of [`KeyValueStore`].
+///
+/// It keeps values in a `HashMap` guarded by a mutex and honours TTLs: expired keys are
+/// never returned and are dropped lazily the next time the store is accessed. Cloning a
+/// `MemoryStore` is cheap and yields a handle to the same underlying data.
+///
+/// Expiry is measured with `tokio::time::Instant`, so tests running on a paused Tokio
+/// clock can use `tokio::time::advance` to expire keys without sleeping.
+///
+/// # Example
+///
+/// ```
+/// use multitool_hg::rediska::memory::MemoryStore;
+/// use multitool_hg::rediska::store::KeyValueStore;
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let store = MemoryStore::new();
+///     store.set("greeting", "hello", None).await?;
+///     assert_eq!(store.get("greeting").await?, Some("hello".to_string()));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: String,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+impl MemoryStore {
+    /// Creates a new, empty `MemoryStore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Locks the underlying map and removes every expired entry.
+    fn entries(&self) -> MutexGuard<'_, HashMap<String, Entry>> {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        entries.retain(|_, entry| !entry.is_expired(now));
+        entries
+    }
+}
+
+#[async_trait]
+impl KeyValueStore for MemoryStore {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        Ok(self.entries().get(key).map(|entry| entry.value.clone()))
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Option<u64>) -> anyhow::Result<()> {
+        let expires_at = ttl.map(|seconds| Instant::now() + Duration::from_secs(seconds));
+        self.entries().insert(
+            key.to_string(),
+            Entry {
+                value: value.to_string(),
+                expires_at,
+            },
+        );
+        Ok(())
+    }
+
+    fn scan<'a>(&'a self, pattern: &str, _count: usize) -> BoxStream<'a, anyhow::Result<String>> {
+        let keys: Vec<String> = self
+            .entries()
+            .keys()
+            .filter(|key| glob_match(pattern.as_bytes(), key.as_bytes()))
+            .cloned()
+            .collect();
+        stream::iter(keys.into_iter().map(Ok)).boxed()
+    }
+
+    async fn delete_by_pattern(&self, pattern: &str, _batch_size: usize) -> anyhow::Result<u64> {
+        let mut entries = self.entries();
+        let before = entries.len();
+        entries.retain(|key, _| !glob_match(pattern.as_bytes(), key.as_bytes()));
+        Ok((before - entries.len()) as u64)
+    }
+}
+
+/// Matches `string` against a Redis glob-style `pattern`.
+///
+/// Supports the same syntax as Redis `KEYS`/`SCAN`: `*`, `?`, character classes such as
+/// `[abc]`, `[^abc]` and `[a-z]`, and `\` to escape a special character.
+fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // Position to resume from after the last `*`: (pattern index after `*`, string index).
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while s < string.len() {
+        let matched = match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p + 1, s));
+                p += 1;
+                continue;
+            }
+            Some(b'?') => Some(p + 1),
+            Some(b'[') => match_class(pattern, p, string[s]),
+            Some(b'\\') if p + 1 < pattern.len() => (pattern[p + 1] == string[s]).then_some(p + 2),
+            Some(&c) => (c == string[s]).then_some(p + 1),
+            None => None,
+        };
+
+        match (matched, backtrack) {
+            (Some(next), _) => {
+                p = next;
+                s += 1;
+            }
+            (None, Some((star_p, star_s))) => {
+                p = star_p;
+                s = star_s + 1;
+                backtrack = Some((star_p, star_s + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Matches `c` against the character class starting at `pattern[start]` (a `[`).
+///
+/// Returns the index just past the closing `]` on a match, or `None` otherwise.
+fn match_class(pattern: &[u8], start: usize, c: u8) -> Option<usize> {
+    let mut p = start + 1;
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+
+    let mut matched = false;
+    while p < pattern.len() && pattern[p] != b']' {
+        if pattern[p] == b'\\' && p + 1 < pattern.len() {
+            matched |= pattern[p + 1] == c;
+            p += 2;
+        } else if p + 2 < pattern.len() && pattern[p + 1] == b'-' && pattern[p + 2] != b']' {
+            let (low, high) = if pattern[p] <= pattern[p + 2] {
+                (pattern[p], pattern[p + 2])
+            } else {
+                (pattern[p + 2], pattern[p])
+            };
+            matched |= (low..=high).contains(&c);
+            p += 3;
+        } else {
+            matched |= pattern[p] == c;
+            p += 1;
+        }
+    }
+
+    // Like Redis, an unterminated class is treated as if it were closed at the end of the pattern.
+    (matched != negate).then_some((p + 1).min(pattern.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::TryStreamExt;
+    use super::*;
+
+    /// Test that a stored value can be read back.
+    #[tokio::test]
+    async fn test_set_and_get() {
+        let store = MemoryStore::new();
+        store.set("key", "value", None).await.unwrap();
+        assert_eq!(store.get("key").await.unwrap(), Some("value".to_string()));
+        assert_eq!(store.get("missing").await.unwrap(), None);
+    }
+
+    /// Test that keys with a TTL disappear once it elapses.
+    #[tokio::test(start_paused = true)]
+    async fn test_ttl_expiry() {
+        let store = MemoryStore::new();
+        store.set("short", "value", Some(10)).await.unwrap();
+        store.set("forever", "value", None).await.unwrap();
+
+        tokio::time::advance(Duration::from_secs(9)).await;
+        assert!(store.get("short").await.unwrap().is_some());
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(store.get("short").await.unwrap(), None);
+        assert!(store.get("forever").await.unwrap().is_some());
+    }
+
+    /// Test that overwriting a key without a TTL clears the previous expiry.
+    #[tokio::test(start_paused = true)]
+    async fn test_set_overwrites_ttl() {
+        let store = MemoryStore::new();
+        store.set("key", "old", Some(1)).await.unwrap();
+        store.set("key", "new", None).await.unwrap();
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(store.get("key").await.unwrap(), Some("new".to_string()));
+    }
+
+    /// Test scanning and deleting keys by pattern, skipping expired keys.
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_and_delete_by_pattern() {
+        let store = MemoryStore::new();
+        store.set("user:1", "a", None).await.unwrap();
+        store.set("user:2", "b", None).await.unwrap();
+        store.set("user:3", "c", Some(1)).await.unwrap();
+        store.set("order:1", "d", None).await.unwrap();
+        tokio::time::advance(Duration::from_secs(1)).await;
+
+        let mut keys: Vec<String> = store.scan("user:*", 10).try_collect().await.unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["user:1", "user:2"]);
+
+        assert_eq!(store.delete_by_pattern("user:*", 10).await.unwrap(), 2);
+        assert_eq!(store.get("user:1").await.unwrap(), None);
+        assert!(store.get("order:1").await.unwrap().is_some());
+    }
+
+    /// Test clones share the same data.
+    #[tokio::test]
+    async fn test_clone_shares_data() {
+        let store = MemoryStore::new();
+        let clone = store.clone();
+        clone.set("key", "value", None).await.unwrap();
+        assert_eq!(store.get("key").await.unwrap(), Some("value".to_string()));
+    }
+
+    /// Test Redis glob pattern semantics.
+    #[test]
+    fn test_glob_match() {
+        let cases: &[(&str, &str, bool)] = &[
+            ("*", "anything", true),
+            ("*", "", true),
+            ("user:*", "user:42", true),
+            ("user:*", "order:42", false),
+            ("h?llo", "hello", true),
+            ("h?llo", "hllo", false),
+            ("h*llo", "heeeello", true),
+            ("h[ae]llo", "hallo", true),
+            ("h[ae]llo", "hillo", false),
+            ("h[^e]llo", "hallo", true),
+            ("h[^e]llo", "hello", false),
+            ("h[a-b]llo", "hbllo", true),
+            ("h[a-b]llo", "hcllo", false),
+            ("h\\*llo", "h*llo", true),
+            ("h\\*llo", "hello", false),
+            ("*:*:end", "a:b:c:end", true),
+            ("a*b", "acbd", false),
+        ];
+
+        for (pattern, string, expected) in cases {
+            assert_eq!(
+                glob_match(pattern.as_bytes(), string.as_bytes()),
+                *expected,
+                "pattern {:?} against {:?}",
+                pattern,
+                string
+            );
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod config;
 pub mod client;
+pub mod manager;
+pub mod store;
+pub mod memory;
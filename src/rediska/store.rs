@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+
+use crate::rediska::client::Rediska;
+
+/// `KeyValueStore` abstracts the key-value operations offered by `Rediska`.
+///
+/// Services that depend on this trait instead of `Rediska` directly can be given
+/// either a real Redis-backed client or the in-process [`MemoryStore`](crate::rediska::memory::MemoryStore),
+/// which makes it possible to unit test them without a running Redis server.
+///
+/// The trait is object safe, so it can be used as `Arc<dyn KeyValueStore>`.
+///
+/// Only string values are covered. The trait has no operations that write hashes, sets
+/// or sorted sets, so it leaves out `Rediska::hscan`, `Rediska::sscan` and `Rediska::zscan`
+/// as well: a `MemoryStore` could never hold anything for them to return. Services that
+/// work with those types should depend on `Rediska` directly.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use multitool_hg::rediska::memory::MemoryStore;
+/// use multitool_hg::rediska::store::KeyValueStore;
+///
+/// struct SessionService {
+///     store: Arc<dyn KeyValueStore>,
+/// }
+///
+/// impl SessionService {
+///     async fn touch(&self, id: &str) -> anyhow::Result<()> {
+///         self.store.set(&format!("session:{}", id), "active", Some(3600)).await
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> anyhow::Result<()> {
+///     let service = SessionService { store: Arc::new(MemoryStore::new()) };
+///     service.touch("42").await?;
+///     assert_eq!(service.store.get("session:42").await?, Some("active".to_string()));
+///     Ok(())
+/// }
+/// ```
+#[async_trait]
+pub trait KeyValueStore: Send + Sync {
+    /// Retrieves the value stored under `key`, or `None` if the key does not exist or has expired.
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>>;
+
+    /// Stores `value` under `key` with an optional TTL in seconds. If `ttl` is `None`, the key will not expire.
+    async fn set(&self, key: &str, value: &str, ttl: Option<u64>) -> anyhow::Result<()>;
+
+    /// Iterates over the keys matching the glob-style `pattern`.
+    ///
+    /// `count` is a hint for how many keys are inspected per batch; backends are free to ignore it.
+    fn scan<'a>(&'a self, pattern: &str, count: usize) -> BoxStream<'a, anyhow::Result<String>>;
+
+    /// Deletes every key matching the glob-style `pattern` and returns the number of removed keys.
+    async fn delete_by_pattern(&self, pattern: &str, batch_size: usize) -> anyhow::Result<u64>;
+}
+
+#[async_trait]
+impl KeyValueStore for Rediska {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        Rediska::get(self, key).await
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Option<u64>) -> anyhow::Result<()> {
+        Rediska::set(self, key, value, ttl).await
+    }
+
+    fn scan<'a>(&'a self, pattern: &str, count: usize) -> BoxStream<'a, anyhow::Result<String>> {
+        Rediska::scan(self, pattern, count).boxed()
+    }
+
+    async fn delete_by_pattern(&self, pattern: &str, batch_size: usize) -> anyhow::Result<u64> {
+        Rediska::delete_by_pattern(self, pattern, batch_size).await
+    }
+}